@@ -0,0 +1,3 @@
+DROP TABLE task_tags;
+DROP INDEX IF EXISTS tag_name_idx;
+DROP TABLE tags;
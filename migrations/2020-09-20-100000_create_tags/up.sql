@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS tags (
+	id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	name TEXT NOT NULL
+);
+CREATE UNIQUE INDEX tag_name_idx ON tags(name);
+
+CREATE TABLE IF NOT EXISTS task_tags (
+	task_id INTEGER NOT NULL REFERENCES suaide(id),
+	tag_id INTEGER NOT NULL REFERENCES tags(id),
+	PRIMARY KEY (task_id, tag_id)
+);
@@ -0,0 +1,3 @@
+ALTER TABLE suaide DROP COLUMN project_id;
+DROP INDEX IF EXISTS project_name_idx;
+DROP TABLE projects;
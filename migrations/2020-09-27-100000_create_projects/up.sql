@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS projects (
+	id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	name TEXT NOT NULL,
+	prefix TEXT NOT NULL DEFAULT '',
+	active BOOLEAN NOT NULL DEFAULT 0
+);
+CREATE UNIQUE INDEX project_name_idx ON projects(name);
+
+ALTER TABLE suaide ADD COLUMN project_id INTEGER REFERENCES projects(id);
@@ -0,0 +1 @@
+ALTER TABLE suaide ADD COLUMN blocked_reason TEXT;
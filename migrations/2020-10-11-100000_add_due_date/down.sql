@@ -0,0 +1 @@
+ALTER TABLE suaide DROP COLUMN due;
@@ -0,0 +1 @@
+ALTER TABLE suaide ADD COLUMN priority SMALLINT;
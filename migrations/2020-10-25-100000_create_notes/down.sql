@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS note_task_idx;
+DROP TABLE notes;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS notes (
+	id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	task_id INTEGER NOT NULL REFERENCES suaide(id),
+	body TEXT NOT NULL,
+	created BIGINT NOT NULL
+);
+CREATE INDEX note_task_idx ON notes(task_id);
@@ -0,0 +1 @@
+ALTER TABLE suaide ADD COLUMN parent_id INTEGER REFERENCES suaide(id);
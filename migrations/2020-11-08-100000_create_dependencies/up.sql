@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS task_dependencies (
+	task_id INTEGER NOT NULL REFERENCES suaide(id),
+	depends_on INTEGER NOT NULL REFERENCES suaide(id),
+	PRIMARY KEY (task_id, depends_on)
+);
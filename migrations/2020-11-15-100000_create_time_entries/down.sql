@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS time_entry_task_idx;
+DROP TABLE time_entries;
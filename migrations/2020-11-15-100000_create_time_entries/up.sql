@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS time_entries (
+	id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	task_id INTEGER NOT NULL REFERENCES suaide(id),
+	started BIGINT NOT NULL,
+	stopped BIGINT
+);
+CREATE INDEX time_entry_task_idx ON time_entries(task_id);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS task_event_task_idx;
+DROP TABLE task_events;
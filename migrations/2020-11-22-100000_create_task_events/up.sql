@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS task_events (
+	id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	task_id INTEGER NOT NULL REFERENCES suaide(id),
+	field TEXT NOT NULL,
+	old_value TEXT,
+	new_value TEXT,
+	created BIGINT NOT NULL
+);
+CREATE INDEX task_event_task_idx ON task_events(task_id);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS journal (
+	id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	command TEXT NOT NULL,
+	before_tasks TEXT NOT NULL,
+	after_tasks TEXT NOT NULL,
+	created BIGINT NOT NULL,
+	undone BOOLEAN NOT NULL DEFAULT 0
+);
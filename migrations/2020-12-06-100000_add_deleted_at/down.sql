@@ -0,0 +1 @@
+ALTER TABLE suaide DROP COLUMN deleted_at;
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS task_search_note_delete;
+DROP TRIGGER IF EXISTS task_search_note_update;
+DROP TRIGGER IF EXISTS task_search_note_insert;
+DROP TRIGGER IF EXISTS task_search_delete;
+DROP TRIGGER IF EXISTS task_search_update;
+DROP TRIGGER IF EXISTS task_search_insert;
+DROP TABLE IF EXISTS task_search;
@@ -0,0 +1,44 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS task_search USING fts5(
+	ticket,
+	description,
+	notes,
+	tokenize = 'porter unicode61'
+);
+
+INSERT INTO task_search(rowid, ticket, description, notes)
+	SELECT id, COALESCE(ticket, ''), description,
+		COALESCE((SELECT GROUP_CONCAT(body, ' ') FROM notes WHERE notes.task_id = suaide.id), '')
+	FROM suaide;
+
+CREATE TRIGGER task_search_insert AFTER INSERT ON suaide BEGIN
+	INSERT OR REPLACE INTO task_search(rowid, ticket, description, notes)
+		VALUES (new.id, COALESCE(new.ticket, ''), new.description,
+			COALESCE((SELECT GROUP_CONCAT(body, ' ') FROM notes WHERE notes.task_id = new.id), ''));
+END;
+
+CREATE TRIGGER task_search_update AFTER UPDATE OF ticket, description ON suaide BEGIN
+	UPDATE task_search SET ticket = COALESCE(new.ticket, ''), description = new.description
+		WHERE rowid = new.id;
+END;
+
+CREATE TRIGGER task_search_delete AFTER DELETE ON suaide BEGIN
+	DELETE FROM task_search WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER task_search_note_insert AFTER INSERT ON notes BEGIN
+	UPDATE task_search
+		SET notes = COALESCE((SELECT GROUP_CONCAT(body, ' ') FROM notes WHERE task_id = new.task_id), '')
+		WHERE rowid = new.task_id;
+END;
+
+CREATE TRIGGER task_search_note_update AFTER UPDATE ON notes BEGIN
+	UPDATE task_search
+		SET notes = COALESCE((SELECT GROUP_CONCAT(body, ' ') FROM notes WHERE task_id = new.task_id), '')
+		WHERE rowid = new.task_id;
+END;
+
+CREATE TRIGGER task_search_note_delete AFTER DELETE ON notes BEGIN
+	UPDATE task_search
+		SET notes = COALESCE((SELECT GROUP_CONCAT(body, ' ') FROM notes WHERE task_id = old.task_id), '')
+		WHERE rowid = old.task_id;
+END;
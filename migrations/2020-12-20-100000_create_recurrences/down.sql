@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS recurrence_task_idx;
+DROP TABLE recurrences;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS recurrences (
+	id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	task_id INTEGER NOT NULL REFERENCES suaide(id),
+	rule TEXT NOT NULL,
+	next_due BIGINT NOT NULL,
+	last_task_id INTEGER NOT NULL REFERENCES suaide(id)
+);
+CREATE UNIQUE INDEX recurrence_task_idx ON recurrences(task_id);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS uuid_idx;
+ALTER TABLE suaide DROP COLUMN uuid;
@@ -0,0 +1,2 @@
+ALTER TABLE suaide ADD COLUMN uuid TEXT;
+CREATE UNIQUE INDEX uuid_idx ON suaide(uuid);
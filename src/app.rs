@@ -1,7 +1,10 @@
-use clap::{App, AppSettings, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches};
 use std::io;
 
-use crate::domain::SuaideError;
+use crate::common::clock::FixedClock;
+use crate::common::storage::materialise_recurrences;
+use crate::common::time::parse_date;
+use crate::domain::{OutputFormat, SuaideError};
 use crate::state::State;
 use crate::subcommands::*;
 
@@ -18,6 +21,21 @@ pub fn build_app<'a>() -> App<'a, 'static> {
         .subcommand(close::app())
         .subcommand(status::app())
         .subcommand(stand_up::app())
+        .subcommand(project::app())
+        .subcommand(note::app())
+        .subcommand(link::app())
+        .subcommand(unlink::app())
+        .subcommand(start::app())
+        .subcommand(stop::app())
+        .subcommand(report::app())
+        .subcommand(history::app())
+        .subcommand(undo::app())
+        .subcommand(redo::app())
+        .subcommand(trash::app())
+        .subcommand(search::app())
+        .subcommand(recur::app())
+        .subcommand(export::app())
+        .subcommand(import::app())
         .arg(
             Arg::with_name("prefix")
                 .help("Overwrite the ticket prefix")
@@ -25,6 +43,21 @@ pub fn build_app<'a>() -> App<'a, 'static> {
                 .short("p")
                 .long("prefix"),
         )
+        .arg(
+            Arg::with_name("as_of")
+                .help("Run the command as if it was the given date (YYYY-MM-DD or DD MMM YYYY)")
+                .takes_value(true)
+                .long("as-of")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .help("The format to write output in")
+                .takes_value(true)
+                .long("output")
+                .possible_values(&["text", "json"])
+                .global(true),
+        )
 }
 
 pub(crate) fn handle_matches<'a, W>(
@@ -40,7 +73,26 @@ where
         state.set_prefix(prefix.to_string());
     }
 
-    match matches.subcommand() {
+    let mut materialise = false;
+    if let (command, Some(sub_matches)) = matches.subcommand() {
+        if let Some(output) = sub_matches.value_of("output") {
+            state.set_output_format(OutputFormat::from(output));
+        }
+        if let Some(as_of) = sub_matches.value_of("as_of") {
+            let date = parse_date(as_of)?;
+            state.set_clock(Box::new(FixedClock::end_of_day(date)?));
+        }
+        materialise = materialises_recurrences(command, sub_matches);
+    }
+
+    if materialise {
+        materialise_recurrences(
+            state.now().date(),
+            state.now().timestamp(),
+            state.get_conn(),
+        )?;
+    }
+    let result = match matches.subcommand() {
         ("add", Some(matches)) => add::handler(matches, state),
         ("edit", Some(matches)) => edit::handler(matches, state),
         ("list", Some(matches)) => list::handler(matches, state),
@@ -48,6 +100,94 @@ where
         ("close", Some(matches)) => close::handler(matches, state),
         ("status", Some(matches)) => status::handler(matches, state),
         ("standup", Some(matches)) => stand_up::handler(matches, state),
+        ("project", Some(matches)) => project::handler(matches, state),
+        ("note", Some(matches)) => note::handler(matches, state),
+        ("link", Some(matches)) => link::handler(matches, state),
+        ("unlink", Some(matches)) => unlink::handler(matches, state),
+        ("start", Some(matches)) => start::handler(matches, state),
+        ("stop", Some(matches)) => stop::handler(matches, state),
+        ("report", Some(matches)) => report::handler(matches, state),
+        ("history", Some(matches)) => history::handler(matches, state),
+        ("undo", Some(matches)) => undo::handler(matches, state),
+        ("redo", Some(matches)) => redo::handler(matches, state),
+        ("trash", Some(matches)) => trash::handler(matches, state),
+        ("search", Some(matches)) => search::handler(matches, state),
+        ("recur", Some(matches)) => recur::handler(matches, state),
+        ("export", Some(matches)) => export::handler(matches, state),
+        ("import", Some(matches)) => import::handler(matches, state),
         _ => Err(SuaideError::SubCommandNotFound),
+    };
+    if materialise && result.is_ok() {
+        materialise_recurrences(
+            state.now().date(),
+            state.now().timestamp(),
+            state.get_conn(),
+        )?;
+    }
+    result
+}
+
+/// Repeating tasks are kept up to date around the commands that change or show
+/// tasks, but never when looking back at a past date or during a dry run
+fn materialises_recurrences(command: &str, matches: &ArgMatches) -> bool {
+    let changes_or_shows_tasks = matches!(
+        command,
+        "add"
+            | "edit"
+            | "list"
+            | "remove"
+            | "close"
+            | "status"
+            | "standup"
+            | "note"
+            | "link"
+            | "unlink"
+            | "start"
+            | "stop"
+            | "trash"
+            | "search"
+            | "recur"
+            | "import"
+    );
+    changes_or_shows_tasks && !matches.is_present("as_of") && !matches.is_present("dry_run")
+}
+
+#[cfg(test)]
+mod test_app {
+    use super::*;
+
+    fn materialises(args: Vec<&str>) -> bool {
+        let matches = build_app().get_matches_from(args);
+        match matches.subcommand() {
+            (command, Some(sub_matches)) => materialises_recurrences(command, sub_matches),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn materialises_recurrences_around_commands_that_change_or_show_tasks() {
+        assert!(materialises(vec!["suaide", "list"]));
+        assert!(materialises(vec!["suaide", "close", "1234"]));
+        assert!(materialises(vec!["suaide", "import", "tasks.csv"]));
+    }
+
+    #[test]
+    fn doesnt_materialise_recurrences_otherwise() {
+        assert!(!materialises(vec!["suaide", "undo"]));
+        assert!(!materialises(vec!["suaide", "redo"]));
+        assert!(!materialises(vec!["suaide", "history", "1234"]));
+        assert!(!materialises(vec!["suaide", "export"]));
+        assert!(!materialises(vec![
+            "suaide",
+            "import",
+            "tasks.csv",
+            "--dry-run"
+        ]));
+        assert!(!materialises(vec![
+            "suaide",
+            "list",
+            "--as-of",
+            "2020-10-01"
+        ]));
     }
 }
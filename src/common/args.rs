@@ -0,0 +1,87 @@
+use chrono::prelude::*;
+use clap::{Arg, ArgMatches};
+
+use crate::common::time::{calculate_duration_from_dates, calculate_duration_from_timeframe};
+use crate::domain::{SuaideError, Timeframe};
+
+/// Every value accepted by a `timeframe` argument
+pub(crate) const TIMEFRAMES: [&str; 8] = [
+    "today",
+    "yesterday",
+    "week",
+    "lastweek",
+    "month",
+    "overdue",
+    "upcoming",
+    "all",
+];
+
+/// Every value accepted by a status filter, see `Status::from_filter`
+pub(crate) const STATUS_FILTERS: [&str; 8] = [
+    "open",
+    "in-progress",
+    "blocked",
+    "closed",
+    "cancelled",
+    "active",
+    "done",
+    "all",
+];
+
+/// Collects every value passed to a (possibly repeated) argument
+pub(crate) fn values_to_vec(matches: &ArgMatches, key: &str) -> Vec<String> {
+    matches
+        .values_of(key)
+        .map(|values| values.map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// A label argument that can be repeated or given a comma separated list, its
+/// long flag is its name unless it's overridden with `.long()`
+pub(crate) fn tags_arg<'a>(name: &'a str, help: &'static str) -> Arg<'a, 'static> {
+    Arg::with_name(name)
+        .long(name)
+        .help(help)
+        .multiple(true)
+        .number_of_values(1)
+        .use_delimiter(true)
+        .takes_value(true)
+}
+
+/// Searches between two dates instead of a `timeframe`
+pub(crate) fn duration_arg<'a>() -> Arg<'a, 'static> {
+    Arg::with_name("duration")
+        .long("duration")
+        .short("d")
+        .conflicts_with("timeframe")
+        .number_of_values(2)
+        .next_line_help(true)
+        .long_help(
+            "Search for all tasks between two dates. \nDates should be provided in one of the following formats \"YYYY-MM-DD\" or \"DD mmm YYYY\"\nExample: 2020-01-01 or 1 Jan 2020\n",
+        )
+        .takes_value(true)
+}
+
+/// Works out the window picked by the `timeframe` or `duration` arguments, and
+/// whether it applies to due dates rather than when tasks were worked on
+pub(crate) fn window_from_matches(
+    matches: &ArgMatches,
+    now: DateTime<Local>,
+) -> Result<(i64, i64, bool), SuaideError> {
+    if let Some(duration_iter) = matches.values_of("duration") {
+        let duration: Vec<&str> = duration_iter.collect();
+        if duration.len() != 2 {
+            return Err(SuaideError::IncorrectArgs);
+        }
+        let (start, end) = calculate_duration_from_dates(duration[0], duration[1])?;
+        return Ok((start, end, false));
+    }
+    match matches.value_of("timeframe") {
+        Some("all") | None => Ok((0, now.timestamp(), false)),
+        Some(tf) => {
+            let timeframe = Timeframe::from(tf);
+            let (start, end) = calculate_duration_from_timeframe(now.date(), timeframe);
+            Ok((start, end, timeframe.is_due_based()))
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::common::time::local_datetime;
+use crate::domain::SuaideError;
+
+/// The source of the current time, so that anything time sensitive can be
+/// run against a fixed point in time
+pub(crate) trait Clock {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+pub(crate) struct FixedClock {
+    now: DateTime<Local>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Local>) -> Self {
+        Self { now }
+    }
+
+    /// A clock fixed to the very end of the given day, so everything that
+    /// happened on that day is considered to be in the past
+    pub fn end_of_day(date: NaiveDate) -> Result<Self, SuaideError> {
+        local_datetime(date.and_hms(23, 59, 59))
+            .map(Self::new)
+            .ok_or_else(|| SuaideError::LocalTimeError(date.to_string()))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        self.now
+    }
+}
+
+#[cfg(test)]
+mod test_clock {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    use crate::common::testing::in_time_zone;
+
+    #[test]
+    fn end_of_day_is_the_last_second() {
+        let clock = FixedClock::end_of_day(NaiveDate::from_ymd(2020, 10, 1)).unwrap();
+        assert_eq!(clock.now(), Local.ymd(2020, 10, 1).and_hms(23, 59, 59));
+    }
+
+    #[test]
+    fn end_of_a_skipped_day() {
+        if !in_time_zone(module_path!(), "end_of_a_skipped_day", "Pacific/Apia") {
+            return;
+        }
+        // Samoa skipped the 30th of December 2011 when it crossed the date line
+        let clock = FixedClock::end_of_day(NaiveDate::from_ymd(2011, 12, 30)).unwrap();
+        assert_eq!(clock.now(), Local.ymd(2011, 12, 31).and_hms(0, 0, 0));
+    }
+}
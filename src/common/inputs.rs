@@ -1,15 +1,141 @@
-use dialoguer::Select;
+use dialoguer::{Confirm, Input, Select};
+use std::io;
 
-use crate::domain::Status;
+use crate::common::storage::get_subtasks;
+use crate::domain::{OutputFormat, Status, SuaideError, Task};
+use crate::state::State;
 
-pub(crate) fn get_state_input(existing_field: Status) -> Status {
-    // @todo - doesn't seem to be a viable way to test Dialoguer?
-    if cfg!(test) {
-        return existing_field;
-    };
-    let options = vec![
+/// Abstracts every interactive prompt so flows can be driven by scripted
+/// answers within the tests
+pub(crate) trait Prompter {
+    fn input(
+        &mut self,
+        prompt: &str,
+        initial: &str,
+        allow_empty: bool,
+    ) -> Result<String, SuaideError>;
+
+    fn select(&mut self, items: &[String], default: usize) -> Result<usize, SuaideError>;
+
+    fn confirm(&mut self, prompt: &str, default: bool) -> Result<bool, SuaideError>;
+}
+
+// Tests are always given a scripted prompter instead
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) struct DialoguerPrompter;
+
+impl Prompter for DialoguerPrompter {
+    fn input(
+        &mut self,
+        prompt: &str,
+        initial: &str,
+        allow_empty: bool,
+    ) -> Result<String, SuaideError> {
+        let mut input = Input::<String>::new();
+        let input = input
+            .with_prompt(prompt)
+            .allow_empty(allow_empty)
+            .with_initial_text(initial);
+        Ok(input.interact()?)
+    }
+
+    fn select(&mut self, items: &[String], default: usize) -> Result<usize, SuaideError> {
+        Ok(Select::new().items(items).default(default).interact()?)
+    }
+
+    fn confirm(&mut self, prompt: &str, default: bool) -> Result<bool, SuaideError> {
+        let mut confirmation = Confirm::new();
+        confirmation.default(default);
+        Ok(confirmation.with_prompt(prompt).interact()?)
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+enum ScriptedAnswer {
+    Input(String),
+    Select(String),
+    Confirm(bool),
+}
+
+/// Answers prompts from a pre-defined script, panicking if the flow asks for
+/// something other than what the test expects
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct ScriptedPrompter {
+    answers: std::collections::VecDeque<ScriptedAnswer>,
+}
+
+#[cfg(test)]
+impl ScriptedPrompter {
+    pub fn input(mut self, answer: &str) -> Self {
+        self.answers
+            .push_back(ScriptedAnswer::Input(answer.to_string()));
+        self
+    }
+
+    /// Selects the first item containing `answer`
+    pub fn select(mut self, answer: &str) -> Self {
+        self.answers
+            .push_back(ScriptedAnswer::Select(answer.to_string()));
+        self
+    }
+
+    pub fn confirm(mut self, answer: bool) -> Self {
+        self.answers.push_back(ScriptedAnswer::Confirm(answer));
+        self
+    }
+
+    fn next(&mut self, prompt: &str) -> ScriptedAnswer {
+        self.answers
+            .pop_front()
+            .unwrap_or_else(|| panic!("No scripted answer for prompt: {}", prompt))
+    }
+}
+
+#[cfg(test)]
+impl Prompter for ScriptedPrompter {
+    fn input(
+        &mut self,
+        prompt: &str,
+        _initial: &str,
+        _allow_empty: bool,
+    ) -> Result<String, SuaideError> {
+        match self.next(prompt) {
+            ScriptedAnswer::Input(answer) => Ok(answer),
+            answer => panic!("Expected input for prompt {}, got {:?}", prompt, answer),
+        }
+    }
+
+    fn select(&mut self, items: &[String], _default: usize) -> Result<usize, SuaideError> {
+        match self.next("select") {
+            ScriptedAnswer::Select(answer) => Ok(items
+                .iter()
+                .position(|item| item.contains(&answer))
+                .unwrap_or_else(|| panic!("{} is not one of {:?}", answer, items))),
+            answer => panic!("Expected a selection, got {:?}", answer),
+        }
+    }
+
+    fn confirm(&mut self, prompt: &str, _default: bool) -> Result<bool, SuaideError> {
+        match self.next(prompt) {
+            ScriptedAnswer::Confirm(answer) => Ok(answer),
+            answer => panic!(
+                "Expected confirmation for prompt {}, got {:?}",
+                prompt, answer
+            ),
+        }
+    }
+}
+
+pub(crate) fn get_state_input<W: io::Write>(
+    state: &mut State<W>,
+    existing_field: Status,
+) -> Result<Status, SuaideError> {
+    let options = [
         Status::Open,
         Status::InProgress,
+        Status::Blocked,
         Status::Closed,
         Status::Cancelled,
     ];
@@ -18,10 +144,51 @@ pub(crate) fn get_state_input(existing_field: Status) -> Status {
         .iter()
         .position(|s| *s == existing_field)
         .expect("Status should always be present");
-    let select = Select::new()
-        .items(&option_text)
-        .default(current)
-        .interact()
-        .unwrap();
-    options[select]
+    let select = state.prompter().select(&option_text, current)?;
+    Ok(options[select])
+}
+
+/// Uses the given reason or asks for one, a blocked task always needs a reason
+pub(crate) fn get_blocked_reason<W: io::Write>(
+    state: &mut State<W>,
+    reason: Option<&str>,
+    existing_reason: Option<String>,
+) -> Result<String, SuaideError> {
+    let reason = match reason {
+        Some(reason) => reason.to_string(),
+        None => state.get_input("blocked reason", existing_reason)?,
+    };
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(SuaideError::BlockedReasonRequired);
+    }
+    Ok(reason.to_string())
+}
+
+/// Closing a task with open subtasks has to be confirmed, or forced when the
+/// output isn't meant for a person. Subtasks in `also_closing` are being closed
+/// alongside it so they don't count
+pub(crate) fn confirm_closing_parent<W: io::Write>(
+    state: &mut State<W>,
+    task: &Task,
+    also_closing: &[i32],
+    force: bool,
+) -> Result<(), SuaideError> {
+    if force {
+        return Ok(());
+    }
+    let open = get_subtasks(task.id, state.get_conn())?
+        .iter()
+        .filter(|t| !t.is_done() && !also_closing.contains(&t.id))
+        .count();
+    if open == 0 {
+        return Ok(());
+    }
+    let prompt = format!("Task has {} open subtask(s), close it anyway?", open);
+    if state.get_output_format() == OutputFormat::Text
+        && state.prompter().confirm(&prompt, false)?
+    {
+        return Ok(());
+    }
+    Err(SuaideError::OpenSubtasksError(open))
 }
@@ -1,6 +1,12 @@
+pub(crate) mod args;
+pub(crate) mod clock;
 mod constants;
 pub(crate) mod inputs;
+pub(crate) mod render;
+pub(crate) mod selection;
 pub(crate) mod storage;
+#[cfg(test)]
+pub(crate) mod testing;
 pub(crate) mod time;
 
 pub(crate) use constants::ADD_PREFIX;
@@ -0,0 +1,514 @@
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::io;
+
+use crate::common::DATE_FORMAT;
+use crate::domain::{
+    OutputFormat, Priority, SearchHit, SearchResultOutput, StandupTemplate, SuaideError, TaskOutput,
+};
+
+/// Writes a value as a single line of JSON
+pub(crate) fn write_json<W: io::Write, T: Serialize>(
+    writer: &mut W,
+    value: &T,
+) -> Result<(), SuaideError> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Renders tasks in the requested output format, every subcommand goes
+/// through this so nothing is written outside of the `State` writer
+pub(crate) struct TaskRenderer {
+    format: OutputFormat,
+    verbose: bool,
+    notes: bool,
+    now: Option<DateTime<Local>>,
+}
+
+impl TaskRenderer {
+    pub fn new(format: OutputFormat, verbose: bool) -> Self {
+        Self {
+            format,
+            verbose,
+            notes: false,
+            now: None,
+        }
+    }
+
+    /// Shows each task's notes even when not verbose
+    pub fn with_notes(mut self, notes: bool) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    /// Highlights any tasks that are overdue as of `now`
+    pub fn with_now(mut self, now: DateTime<Local>) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Reports what a command did, as JSON that's the value and as text it's
+    /// the message
+    pub fn render_message<W: io::Write, T: Serialize>(
+        &self,
+        writer: &mut W,
+        value: &T,
+        message: &str,
+    ) -> Result<(), SuaideError> {
+        match self.format {
+            OutputFormat::Json => write_json(writer, value),
+            OutputFormat::Text => {
+                writeln!(writer, "{}", message)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn render_task<W: io::Write>(
+        &self,
+        writer: &mut W,
+        task: &TaskOutput,
+    ) -> Result<(), SuaideError> {
+        match self.format {
+            OutputFormat::Json => write_json(writer, task),
+            OutputFormat::Text => self.write_task(writer, task),
+        }
+    }
+
+    pub fn render_tasks<W: io::Write>(
+        &self,
+        writer: &mut W,
+        tasks: &[TaskOutput],
+    ) -> Result<(), SuaideError> {
+        match self.format {
+            OutputFormat::Json => write_json(writer, &tasks),
+            OutputFormat::Text => {
+                // Subtasks are nested under their parent when it's also listed
+                let ids: HashSet<i32> = tasks.iter().map(|t| t.id).collect();
+                let roots = tasks
+                    .iter()
+                    .filter(|t| t.parent_id.is_none_or(|parent| !ids.contains(&parent)));
+                for task in roots {
+                    self.write_tree(writer, tasks, task, 0)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Renders search results best match first, each followed by the snippet
+    /// of text that matched with the matched terms highlighted
+    pub fn render_search_results<W: io::Write>(
+        &self,
+        writer: &mut W,
+        results: &[SearchResultOutput],
+    ) -> Result<(), SuaideError> {
+        match self.format {
+            OutputFormat::Json => write_json(writer, &results),
+            OutputFormat::Text => {
+                for result in results {
+                    self.write_task(writer, &result.task)?;
+                    writeln!(writer, "\t{}", highlight_snippet(&result.snippet))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Renders titled groups of tasks, as JSON they're keyed by the lower-cased title
+    pub fn render_sections<W: io::Write>(
+        &self,
+        writer: &mut W,
+        sections: &[(&str, Vec<TaskOutput>)],
+    ) -> Result<(), SuaideError> {
+        match self.format {
+            OutputFormat::Json => {
+                let mut output = Map::new();
+                for (title, tasks) in sections {
+                    output.insert(title.to_lowercase(), serde_json::to_value(tasks)?);
+                }
+                write_json(writer, &Value::Object(output))
+            }
+            OutputFormat::Text => {
+                for (title, tasks) in sections {
+                    writeln!(writer, "=== {} ===", title.bold())?;
+                    for task in tasks {
+                        self.write_task(writer, task)?;
+                    }
+                    writeln!(writer)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Private API
+impl TaskRenderer {
+    fn write_tree<W: io::Write>(
+        &self,
+        writer: &mut W,
+        tasks: &[TaskOutput],
+        task: &TaskOutput,
+        depth: usize,
+    ) -> Result<(), SuaideError> {
+        self.write_task_at(writer, task, depth)?;
+        for subtask in tasks.iter().filter(|t| t.parent_id == Some(task.id)) {
+            self.write_tree(writer, tasks, subtask, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    fn write_task<W: io::Write>(
+        &self,
+        writer: &mut W,
+        task: &TaskOutput,
+    ) -> Result<(), SuaideError> {
+        self.write_task_at(writer, task, 0)
+    }
+
+    fn write_task_at<W: io::Write>(
+        &self,
+        writer: &mut W,
+        task: &TaskOutput,
+        depth: usize,
+    ) -> Result<(), SuaideError> {
+        let ticket = match &task.ticket {
+            Some(ticket) => format!("{}:", ticket),
+            None => format!("#{}:", task.id.to_string().italic()),
+        };
+        let ticket = match task.priority {
+            Some(priority) => format!("{} {}", priority, ticket),
+            None => ticket,
+        };
+        let indent = match depth {
+            0 => String::new(),
+            _ => format!("{}└─ ", "   ".repeat(depth - 1)),
+        };
+        let mut line = format!(
+            "{}[{}] {} {}",
+            indent, task.status, ticket, task.description
+        );
+        if let Some(subtasks) = task.subtasks {
+            line.push_str(&format!(" ({}/{} done)", subtasks.done, subtasks.total));
+        }
+        let is_overdue = self.now.is_some_and(|now| task.is_overdue(now));
+        if is_overdue {
+            line.push_str(&format!(" {}", "(overdue)".red().bold()));
+        }
+        writeln!(writer, "{}", line)?;
+        if let Some(reason) = &task.blocked_reason {
+            writeln!(writer, "\tBlocked: {}", reason.italic())?;
+        }
+        if self.notes && !self.verbose {
+            self.write_notes(writer, task)?;
+        }
+
+        if self.verbose {
+            let opened = format!("Opened: {}", format_date(Some(task.opened)));
+            writeln!(
+                writer,
+                "\t{:30} Closed: {}",
+                opened,
+                format_date(task.closed)
+            )?;
+            if let Some(due) = task.due {
+                let due = format_date(Some(due));
+                if is_overdue {
+                    writeln!(writer, "\tDue: {}", due.red())?;
+                } else {
+                    writeln!(writer, "\tDue: {}", due)?;
+                }
+            }
+            if !task.tags.is_empty() {
+                writeln!(writer, "\tTags: {}", task.tags.join(", ").italic())?;
+            }
+            if !task.blocked_by.is_empty() {
+                writeln!(writer, "\tBlocked by: {}", task.blocked_by.join(", ").red())?;
+            }
+            self.write_notes(writer, task)?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    fn write_notes<W: io::Write>(
+        &self,
+        writer: &mut W,
+        task: &TaskOutput,
+    ) -> Result<(), SuaideError> {
+        if task.notes.is_empty() {
+            return Ok(());
+        }
+        writeln!(writer, "\tNotes:")?;
+        for note in &task.notes {
+            writeln!(
+                writer,
+                "\t  {} {}",
+                format_date(Some(note.created)).italic(),
+                note.body
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn highlight_snippet(snippet: &str) -> String {
+    snippet
+        .split(SearchHit::HIGHLIGHT_START)
+        .enumerate()
+        .map(
+            |(idx, part)| match part.split_once(SearchHit::HIGHLIGHT_END) {
+                Some((matched, rest)) if idx > 0 => format!("{}{}", matched.yellow().bold(), rest),
+                _ => part.to_string(),
+            },
+        )
+        .collect()
+}
+
+/// Renders titled groups of tasks using a stand-up template, `ticket_url` is
+/// used to build ticket links with `{ticket}` replaced by the ticket id
+pub(crate) fn render_template<W: io::Write>(
+    writer: &mut W,
+    template: &StandupTemplate,
+    sections: &[(&str, Vec<TaskOutput>)],
+    ticket_url: Option<&str>,
+) -> Result<(), SuaideError> {
+    for (idx, (title, tasks)) in sections.iter().enumerate() {
+        if idx > 0 {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "{}", fill(&template.section, &[("section", title)]))?;
+        if tasks.is_empty() && !template.empty.is_empty() {
+            writeln!(writer, "{}", template.empty)?;
+        }
+        for task in tasks {
+            writeln!(writer, "{}", fill_task(template, task, ticket_url))?;
+        }
+    }
+    Ok(())
+}
+
+fn fill_task(template: &StandupTemplate, task: &TaskOutput, ticket_url: Option<&str>) -> String {
+    let id = task.id.to_string();
+    let ticket = match &task.ticket {
+        Some(ticket) => ticket.clone(),
+        None => format!("#{}", id),
+    };
+    let ticket_link = match (&task.ticket, ticket_url) {
+        (Some(t), Some(url)) => {
+            let url = fill(url, &[("ticket", t)]);
+            fill(&template.link, &[("ticket", t), ("url", &url)])
+        }
+        _ => ticket.clone(),
+    };
+    let priority = task.priority.map(Priority::name).unwrap_or_default();
+    let subtasks = task
+        .subtasks
+        .map(|s| format!("{}/{}", s.done, s.total))
+        .unwrap_or_default();
+    let notes = task
+        .notes
+        .iter()
+        .map(|note| note.body.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    let line = match (&task.blocked_reason, &template.blocked) {
+        (Some(_), Some(blocked)) => blocked,
+        _ => &template.task,
+    };
+    fill(
+        line,
+        &[
+            ("id", &id),
+            ("ticket", &ticket),
+            ("ticket_link", &ticket_link),
+            ("description", &task.description),
+            ("status", task.status.name()),
+            ("project", task.project.as_deref().unwrap_or_default()),
+            ("tags", &task.tags.join(", ")),
+            ("opened", &format_date(Some(task.opened))),
+            ("closed", &format_date(task.closed)),
+            ("reason", task.blocked_reason.as_deref().unwrap_or_default()),
+            ("due", &format_date(task.due)),
+            ("priority", priority),
+            ("notes", &notes),
+            ("subtasks", &subtasks),
+        ],
+    )
+}
+
+/// Replaces each `{name}` placeholder with its value in a single pass, so any
+/// braces inside the values are written out as they are
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &rest[1..end])
+                .map(|(_, value)| (end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                output.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn format_date(date: Option<DateTime<Local>>) -> String {
+    date.map(|d| d.format(DATE_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test_render_template {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    use crate::domain::{StandupFormat, Status};
+
+    fn task(id: i32, ticket: Option<&str>, status: Status) -> TaskOutput {
+        TaskOutput {
+            id,
+            ticket: ticket.map(String::from),
+            description: "Fix login bug".to_string(),
+            status,
+            opened: Local.ymd(2020, 10, 1).and_hms(9, 0, 0),
+            closed: None,
+            project: None,
+            tags: vec!["backend".to_string()],
+            blocked_reason: None,
+            due: None,
+            priority: None,
+            notes: vec![],
+            parent_id: None,
+            subtasks: None,
+            blocked_by: vec![],
+        }
+    }
+
+    fn render(template: &StandupTemplate, ticket_url: Option<&str>) -> String {
+        let mut writer = Vec::new();
+        let sections = [
+            ("Yesterday", vec![task(1, Some("TASK-1"), Status::Closed)]),
+            ("Today", vec![task(2, None, Status::InProgress)]),
+        ];
+        render_template(&mut writer, template, &sections, ticket_url).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn plain() {
+        let output = render(&StandupFormat::Plain.template(), None);
+        assert_eq!(
+            output,
+            "Yesterday:\n- [Completed] TASK-1: Fix login bug\n\nToday:\n- [In Progress] #2: Fix login bug\n"
+        );
+    }
+
+    #[test]
+    fn markdown_with_ticket_links() {
+        let output = render(
+            &StandupFormat::Markdown.template(),
+            Some("https://jira.example.com/browse/{ticket}"),
+        );
+        assert!(output.contains("### Yesterday"));
+        assert!(output.contains(
+            "- **[TASK-1](https://jira.example.com/browse/TASK-1)** Fix login bug _(Completed)_"
+        ));
+        assert!(output.contains("- **#2** Fix login bug _(In Progress)_"));
+    }
+
+    #[test]
+    fn slack_with_ticket_links() {
+        let output = render(
+            &StandupFormat::Slack.template(),
+            Some("https://jira.example.com/browse/{ticket}"),
+        );
+        assert!(output.contains("*Today*"));
+        assert!(output.contains(
+            "• <https://jira.example.com/browse/TASK-1|TASK-1> Fix login bug `Completed`"
+        ));
+    }
+
+    #[test]
+    fn blocked_tasks_use_the_blocked_line() {
+        let mut blocked = task(4, Some("TASK-4"), Status::Blocked);
+        blocked.blocked_reason = Some("Waiting on review".to_string());
+
+        let mut writer = Vec::new();
+        let sections = [("Blockers", vec![blocked])];
+        render_template(
+            &mut writer,
+            &StandupFormat::Plain.template(),
+            &sections,
+            None,
+        )
+        .unwrap();
+        let output = String::from_utf8(writer).unwrap();
+        assert_eq!(
+            output,
+            "Blockers:\n- [Blocked] TASK-4: Fix login bug (Waiting on review)\n"
+        );
+    }
+
+    #[test]
+    fn placeholders_in_values_are_left_alone() {
+        let mut tricky = task(5, Some("{url}"), Status::Open);
+        tricky.description = "Render {ticket} and {status}".to_string();
+
+        let mut writer = Vec::new();
+        let sections = [("{section}", vec![tricky])];
+        render_template(
+            &mut writer,
+            &StandupFormat::Markdown.template(),
+            &sections,
+            Some("https://jira.example.com/browse/{ticket}"),
+        )
+        .unwrap();
+        let output = String::from_utf8(writer).unwrap();
+        assert_eq!(
+            output,
+            "### {section}\n- **[{url}](https://jira.example.com/browse/{url})** Render {ticket} and {status} _(Open)_\n"
+        );
+    }
+
+    #[test]
+    fn empty_sections_and_custom_fields() {
+        let template = StandupTemplate {
+            section: "## {section}".to_string(),
+            task: "{id} {tags} {opened}".to_string(),
+            blocked: None,
+            link: "{ticket}".to_string(),
+            empty: "n/a".to_string(),
+        };
+        let mut writer = Vec::new();
+        let sections = [
+            ("Yesterday", vec![]),
+            ("Today", vec![task(3, None, Status::Open)]),
+        ];
+        render_template(&mut writer, &template, &sections, None).unwrap();
+        let output = String::from_utf8(writer).unwrap();
+        assert_eq!(
+            output,
+            "## Yesterday\nn/a\n\n## Today\n3 backend 2020-10-01 09:00\n"
+        );
+    }
+}
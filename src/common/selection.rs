@@ -0,0 +1,156 @@
+use clap::{Arg, ArgMatches};
+use colored::Colorize;
+use diesel::prelude::*;
+use std::collections::HashSet;
+use std::io;
+
+use crate::common::args::{tags_arg, values_to_vec, STATUS_FILTERS};
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    apply_deleted_filter, apply_project_filter, apply_status_filter, apply_tag_filters,
+    get_task_outputs,
+};
+use crate::domain::{OutputFormat, Status, SuaideError, Task};
+use crate::state::State;
+
+/// The tasks a command applies to, `bulk` is set when they were picked by
+/// more than a single task argument
+pub(crate) struct Selection {
+    pub(crate) tasks: Vec<Task>,
+    pub(crate) bulk: bool,
+}
+
+/// The arguments used to pick the tasks a command applies to, either by
+/// listing them, by ranges of ids or by filters
+pub(crate) fn selection_args<'a>(help: &'static str) -> Vec<Arg<'a, 'static>> {
+    vec![
+        Arg::with_name("task")
+            .index(1)
+            .multiple(true)
+            .help(help)
+            .long_help(
+                "The tasks to change, either tickets, ids or an inclusive range of ids such as 12..18\n",
+            )
+            .takes_value(true),
+        tags_arg(
+            "tag",
+            "Select every task with this label, can be repeated or comma separated",
+        ),
+        Arg::with_name("status_filter")
+            .long("status")
+            .short("s")
+            .help("Select every task with this status, can be repeated or comma separated")
+            .multiple(true)
+            .number_of_values(1)
+            .use_delimiter(true)
+            .possible_values(&STATUS_FILTERS)
+            .takes_value(true),
+        Arg::with_name("yes")
+            .long("yes")
+            .short("y")
+            .help("Don't ask for confirmation when changing several tasks"),
+    ]
+}
+
+/// Finds every task picked by the given task arguments and the selection
+/// filters, in the order they were picked and without duplicates
+pub(crate) fn resolve_tasks<W: io::Write>(
+    values: &[String],
+    matches: &ArgMatches,
+    state: &State<W>,
+) -> Result<Selection, SuaideError> {
+    use crate::schema::suaide::dsl::{id, suaide};
+
+    let tags = values_to_vec(matches, "tag");
+    let statuses: Vec<Status> = values_to_vec(matches, "status_filter")
+        .iter()
+        .flat_map(|s| Status::from_filter(s))
+        .collect();
+    if values.is_empty() && tags.is_empty() && statuses.is_empty() {
+        return Err(SuaideError::IncorrectArgs);
+    }
+
+    let mut tasks = Vec::new();
+    let mut bulk = values.len() > 1;
+    for value in values {
+        match parse_range(value) {
+            Some((from, to)) => {
+                bulk = true;
+                let query = apply_deleted_filter(suaide.filter(id.between(from, to)).into_boxed());
+                tasks.extend(query.order_by(id.asc()).load::<Task>(state.get_conn())?);
+            }
+            None => tasks.push(state.find_task(value)?),
+        }
+    }
+
+    if !tags.is_empty() || !statuses.is_empty() {
+        bulk = true;
+        let query = apply_deleted_filter(suaide.into_boxed());
+        let query = apply_project_filter(query, state.get_project().map(|p| p.id));
+        let query = apply_status_filter(query, &statuses);
+        tasks.extend(
+            apply_tag_filters(query, &tags, &[])
+                .order_by(id.asc())
+                .load::<Task>(state.get_conn())?,
+        );
+    }
+
+    let mut seen = HashSet::new();
+    tasks.retain(|t| seen.insert(t.id));
+    if tasks.is_empty() {
+        return Err(SuaideError::NotFound);
+    }
+    Ok(Selection { tasks, bulk })
+}
+
+/// Changing several tasks at once lists the tasks that will change and asks
+/// first, unless `--yes` was given or the output isn't meant for a person
+pub(crate) fn confirm_selection<W: io::Write>(
+    state: &mut State<W>,
+    selection: &Selection,
+    action: &str,
+    matches: &ArgMatches,
+) -> Result<bool, SuaideError> {
+    if !selection.bulk || matches.is_present("yes") {
+        return Ok(true);
+    }
+    let count = selection.tasks.len();
+    if state.get_output_format() != OutputFormat::Text {
+        return Err(SuaideError::ConfirmationRequired(count));
+    }
+
+    let output = get_task_outputs(&selection.tasks, state.get_conn())?;
+    writeln!(state.writer(), "{} task(s) will be {}:", count, action)?;
+    TaskRenderer::new(OutputFormat::Text, false)
+        .with_now(state.now())
+        .render_tasks(state.writer(), &output)?;
+    let prompt = format!("{}", "Continue?".bold());
+    state.prompter().confirm(&prompt, false)
+}
+
+/// Parses an inclusive range of ids such as `12..18`
+fn parse_range(value: &str) -> Option<(i32, i32)> {
+    let (from, to) = value.split_at(value.find("..")?);
+    let from = from.trim().parse::<i32>().ok()?;
+    let to = to[2..].trim().parse::<i32>().ok()?;
+    Some((from.min(to), from.max(to)))
+}
+
+#[cfg(test)]
+mod test_parse_range {
+    use super::*;
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("12..18"), Some((12, 18)));
+        assert_eq!(parse_range("18..12"), Some((12, 18)));
+        assert_eq!(parse_range("3..3"), Some((3, 3)));
+    }
+
+    #[test]
+    fn ignores_anything_else() {
+        assert_eq!(parse_range("1234"), None);
+        assert_eq!(parse_range("TASK-1..TASK-4"), None);
+        assert_eq!(parse_range("12.."), None);
+    }
+}
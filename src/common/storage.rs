@@ -1,11 +1,41 @@
+use chrono::prelude::*;
 use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
 
-use crate::domain::{SuaideError, Task};
+use crate::common::time::{end_of_day, timestamp_to_local};
+use crate::domain::{
+    AddDependency, AddJournalEntry, AddNote, AddProject, AddRecurrence, AddTag, AddTask,
+    AddTaskEvent, AddTaskTag, AddTimeEntry, JournalEntry, Note, NoteOutput, Project, Recurrence,
+    RecurrenceRule, SearchHit, Status, SuaideError, SubtaskProgress, Tag, Task, TaskChangeSet,
+    TaskEvent, TaskOutput, TaskSnapshot, TimeEntry,
+};
+use crate::schema::suaide::BoxedQuery;
 
 pub(crate) fn get_task(task: &str, db_conn: &SqliteConnection) -> Result<Task, SuaideError> {
+    find_task(task, false, db_conn)
+}
+
+/// Finds a task that has been moved to the trash
+pub(crate) fn get_trashed_task(
+    task: &str,
+    db_conn: &SqliteConnection,
+) -> Result<Task, SuaideError> {
+    find_task(task, true, db_conn)
+}
+
+fn find_task(task: &str, trashed: bool, db_conn: &SqliteConnection) -> Result<Task, SuaideError> {
     use crate::schema::suaide::dsl::*;
 
-    if let Ok(mut result) = suaide
+    let query = || {
+        if trashed {
+            suaide.filter(deleted_at.is_not_null()).into_boxed()
+        } else {
+            suaide.filter(deleted_at.is_null()).into_boxed()
+        }
+    };
+    if let Ok(mut result) = query()
         .filter(ticket.eq(Some(task)))
         .limit(1)
         .load::<Task>(db_conn)
@@ -16,9 +46,1078 @@ pub(crate) fn get_task(task: &str, db_conn: &SqliteConnection) -> Result<Task, S
         }
     }
     if let Ok(num) = task.parse::<i32>() {
-        if let Ok(task) = suaide.find(num).first(db_conn) {
+        if let Ok(task) = query().filter(id.eq(num)).first(db_conn) {
             return Ok(task);
         }
     }
     Err(SuaideError::NotFound)
 }
+
+/// Every task in the trash, most recently removed first
+pub(crate) fn get_trashed_tasks(db_conn: &SqliteConnection) -> Result<Vec<Task>, SuaideError> {
+    use crate::schema::suaide::dsl::*;
+
+    Ok(suaide
+        .filter(deleted_at.is_not_null())
+        .order_by(deleted_at.desc())
+        .load::<Task>(db_conn)?)
+}
+
+/// Gives every task without a uuid a new one, so a task keeps the same uuid in
+/// each Taskwarrior export. Either every task gets its uuid or none do.
+pub(crate) fn assign_uuids(
+    tasks: &[Task],
+    db_conn: &SqliteConnection,
+) -> Result<Vec<String>, SuaideError> {
+    use crate::schema::suaide::dsl::{suaide, uuid};
+
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        tasks
+            .iter()
+            .map(|task| match &task.uuid {
+                Some(task_uuid) => Ok(task_uuid.clone()),
+                None => {
+                    let task_uuid = Uuid::new_v4().to_hyphenated().to_string();
+                    diesel::update(suaide.find(task.id))
+                        .set(uuid.eq(&task_uuid))
+                        .execute(db_conn)?;
+                    Ok(task_uuid)
+                }
+            })
+            .collect()
+    })
+}
+
+/// Permanently deletes the tasks along with everything attached to them,
+/// including the journal entries that could otherwise undo them back
+pub(crate) fn purge_tasks(tasks: &[i32], db_conn: &SqliteConnection) -> Result<(), SuaideError> {
+    use crate::schema::journal;
+
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        let mut stale = Vec::new();
+        for entry in journal::table.load::<JournalEntry>(db_conn)? {
+            let before = entry.before()?;
+            let after = entry.after()?;
+            if before
+                .iter()
+                .chain(&after)
+                .any(|t| tasks.contains(&t.task.id))
+            {
+                stale.push(entry.id);
+            }
+        }
+        diesel::delete(journal::table.filter(journal::id.eq_any(stale))).execute(db_conn)?;
+        delete_tasks(tasks, db_conn)
+    })
+}
+
+/// Deletes the tasks and every row attached to them. Foreign keys aren't
+/// enforced, so anything left behind would be picked up again by a task that
+/// comes back with the same id through `redo`. Subtasks of a deleted task are
+/// kept as top level tasks, and a repeat whose latest instance is deleted goes
+/// back to pointing at its template as it did when it was first added
+fn delete_tasks(tasks: &[i32], db_conn: &SqliteConnection) -> Result<(), SuaideError> {
+    use crate::schema::{
+        notes, recurrences, suaide, task_dependencies, task_events, task_tags, time_entries,
+    };
+
+    diesel::delete(recurrences::table.filter(recurrences::task_id.eq_any(tasks)))
+        .execute(db_conn)?;
+    diesel::update(recurrences::table.filter(recurrences::last_task_id.eq_any(tasks)))
+        .set(recurrences::last_task_id.eq(recurrences::task_id))
+        .execute(db_conn)?;
+    diesel::update(suaide::table.filter(suaide::parent_id.eq_any(tasks)))
+        .set(suaide::parent_id.eq(None::<i32>))
+        .execute(db_conn)?;
+    diesel::delete(task_tags::table.filter(task_tags::task_id.eq_any(tasks))).execute(db_conn)?;
+    diesel::delete(notes::table.filter(notes::task_id.eq_any(tasks))).execute(db_conn)?;
+    diesel::delete(
+        task_dependencies::table.filter(
+            task_dependencies::task_id
+                .eq_any(tasks)
+                .or(task_dependencies::depends_on.eq_any(tasks)),
+        ),
+    )
+    .execute(db_conn)?;
+    diesel::delete(task_events::table.filter(task_events::task_id.eq_any(tasks)))
+        .execute(db_conn)?;
+    diesel::delete(time_entries::table.filter(time_entries::task_id.eq_any(tasks)))
+        .execute(db_conn)?;
+    diesel::delete(suaide::table.filter(suaide::id.eq_any(tasks))).execute(db_conn)?;
+    Ok(())
+}
+
+/// Makes a task repeat, the task is the template for every instance as well
+/// as being the first of them
+pub(crate) fn add_recurrence(
+    task_id: i32,
+    rule: &RecurrenceRule,
+    next_due: i64,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::recurrences;
+
+    let recurrence = AddRecurrence {
+        task_id,
+        rule: rule.to_string(),
+        next_due,
+        last_task_id: task_id,
+    };
+    diesel::insert_into(recurrences::table)
+        .values(&recurrence)
+        .execute(db_conn)?;
+    Ok(())
+}
+
+pub(crate) fn get_recurrences(db_conn: &SqliteConnection) -> Result<Vec<Recurrence>, SuaideError> {
+    use crate::schema::recurrences::dsl::*;
+
+    Ok(recurrences.order_by(next_due.asc()).load(db_conn)?)
+}
+
+/// Stops a task from repeating, any instances already created are kept
+pub(crate) fn remove_recurrence(
+    template: i32,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::recurrences::dsl::*;
+
+    match diesel::delete(recurrences.filter(task_id.eq(template))).execute(db_conn)? {
+        0 => Err(SuaideError::RecurrenceNotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Creates the next instance of every repeating task whose latest instance is
+/// done, or whose next instance is due by `today`. Occurrences missed entirely
+/// are skipped rather than each getting an instance, and templates in the
+/// trash don't repeat until they're restored
+pub(crate) fn materialise_recurrences(
+    today: Date<Local>,
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<Task>, SuaideError> {
+    use crate::schema::recurrences::dsl::{last_task_id, next_due, recurrences};
+
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        let mut created = Vec::new();
+        for recurrence in get_recurrences(db_conn)? {
+            let template = match get_task_by_id(recurrence.task_id, db_conn) {
+                Ok(template) if template.deleted_at.is_none() => template,
+                _ => continue,
+            };
+            let last_done = get_task_by_id(recurrence.last_task_id, db_conn)
+                .map(|last| last.is_done())
+                .unwrap_or(true);
+            let rule: RecurrenceRule = recurrence.rule.parse()?;
+            let mut due = timestamp_to_local(recurrence.next_due).date();
+            if !last_done && due > today {
+                continue;
+            }
+            while rule.next_after(due) <= today {
+                due = rule.next_after(due);
+            }
+
+            let task = AddTask {
+                description: template.description.clone(),
+                opened: now,
+                project_id: template.project_id,
+                due: Some(end_of_day(due.naive_local())?),
+                priority: template.priority,
+                parent_id: template.parent_id,
+                ..AddTask::default()
+            };
+            diesel::insert_into(crate::schema::suaide::table)
+                .values(&task)
+                .execute(db_conn)?;
+            let instance = get_last_inserted_task(db_conn)?;
+            add_tags_to_task(
+                instance.id,
+                &get_tags_for_task(template.id, db_conn)?,
+                db_conn,
+            )?;
+            record_events(&[AddTaskEvent::added(&instance, now)], db_conn)?;
+            diesel::update(recurrences.find(recurrence.id))
+                .set((
+                    next_due.eq(end_of_day(rule.next_after(due).naive_local())?),
+                    last_task_id.eq(instance.id),
+                ))
+                .execute(db_conn)?;
+            created.push(instance);
+        }
+        // Journalled like any other added task so the instances can be undone
+        record_journal("recur", &[], &created, now, db_conn)?;
+        Ok(created)
+    })
+}
+
+pub(crate) fn get_task_by_id(task: i32, db_conn: &SqliteConnection) -> Result<Task, SuaideError> {
+    use crate::schema::suaide::dsl::*;
+
+    suaide
+        .find(task)
+        .first(db_conn)
+        .optional()?
+        .ok_or(SuaideError::NotFound)
+}
+
+pub(crate) fn get_last_inserted_task(db_conn: &SqliteConnection) -> Result<Task, SuaideError> {
+    use crate::schema::suaide::dsl::*;
+
+    Ok(suaide.order_by(id.desc()).first(db_conn)?)
+}
+
+/// Applies the change set to the task and records each changed field in the
+/// task's history
+pub(crate) fn update_task(
+    task: &Task,
+    change_set: TaskChangeSet,
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::suaide::dsl::*;
+
+    // Diesel refuses to execute an update without any columns to set
+    if change_set.is_empty() {
+        return Ok(());
+    }
+    let events = change_set.events(task, now);
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        diesel::update(suaide.find(task.id))
+            .set(change_set)
+            .execute(db_conn)?;
+        record_events(&events, db_conn)
+    })
+}
+
+/// Applies a change set to each task within a single transaction, journaling
+/// them together so they're undone together, returning the updated tasks
+pub(crate) fn update_tasks<F>(
+    command: &str,
+    tasks: &[Task],
+    now: i64,
+    db_conn: &SqliteConnection,
+    change: F,
+) -> Result<Vec<Task>, SuaideError>
+where
+    F: Fn(&Task) -> TaskChangeSet,
+{
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        let mut updated = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            update_task(task, change(task), now, db_conn)?;
+            updated.push(get_task_by_id(task.id, db_conn)?);
+        }
+        record_journal(command, tasks, &updated, now, db_conn)?;
+        Ok(updated)
+    })
+}
+
+pub(crate) fn record_events(
+    events: &[AddTaskEvent],
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::task_events;
+
+    diesel::insert_into(task_events::table)
+        .values(events)
+        .execute(db_conn)?;
+    Ok(())
+}
+
+pub(crate) fn get_events_for_task(
+    task: i32,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<TaskEvent>, SuaideError> {
+    use crate::schema::task_events::dsl::*;
+
+    Ok(task_events
+        .filter(task_id.eq(task))
+        .order_by((created.asc(), id.asc()))
+        .load::<TaskEvent>(db_conn)?)
+}
+
+/// Status changes between `start` and `end`, excluding the status each task
+/// was added with
+pub(crate) fn get_status_changes_between(
+    start: i64,
+    end: i64,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<TaskEvent>, SuaideError> {
+    use crate::schema::task_events::dsl::*;
+
+    Ok(task_events
+        .filter(field.eq(TaskEvent::STATUS))
+        .filter(old_value.is_not_null())
+        .filter(created.between(start, end))
+        .order_by((created.asc(), id.asc()))
+        .load::<TaskEvent>(db_conn)?)
+}
+
+/// Journals a mutating command so it can be undone, once something else has
+/// changed anything that was undone can no longer be redone
+pub(crate) fn record_journal(
+    command: &str,
+    before: &[Task],
+    after: &[Task],
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    let before: Vec<TaskSnapshot> = before.iter().map(TaskSnapshot::from).collect();
+    let after: Vec<TaskSnapshot> = after.iter().map(TaskSnapshot::from).collect();
+    record_snapshots(command, &before, &after, now, db_conn)
+}
+
+/// Journals a command from snapshots of the tasks, for commands that change
+/// more than the tasks' own fields
+pub(crate) fn record_snapshots(
+    command: &str,
+    before: &[TaskSnapshot],
+    after: &[TaskSnapshot],
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::journal::dsl::{journal, undone};
+
+    if before == after {
+        return Ok(());
+    }
+    let entry = AddJournalEntry::new(command, before, after, now)?;
+    diesel::delete(journal.filter(undone.eq(true))).execute(db_conn)?;
+    diesel::insert_into(journal)
+        .values(entry)
+        .execute(db_conn)?;
+    Ok(())
+}
+
+/// The latest entry that can be undone, or when `is_undone` is set, the entry
+/// that was undone most recently
+pub(crate) fn get_journal_entry(
+    is_undone: bool,
+    db_conn: &SqliteConnection,
+) -> Result<Option<JournalEntry>, SuaideError> {
+    use crate::schema::journal::dsl::*;
+
+    let query = journal.filter(undone.eq(is_undone));
+    let entry = if is_undone {
+        query.order_by(id.asc()).first::<JournalEntry>(db_conn)
+    } else {
+        query.order_by(id.desc()).first::<JournalEntry>(db_conn)
+    };
+    Ok(entry.optional()?)
+}
+
+/// Undoes or redoes the entry by putting each task it touched back the way it
+/// was, returning the tasks as they are now
+pub(crate) fn apply_journal_entry(
+    entry: &JournalEntry,
+    undo: bool,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<Task>, SuaideError> {
+    let (from, to) = if undo {
+        (entry.after()?, entry.before()?)
+    } else {
+        (entry.before()?, entry.after()?)
+    };
+
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        use crate::schema::journal::dsl::{journal, undone};
+
+        let removed: Vec<i32> = from
+            .iter()
+            .filter(|t| !to.iter().any(|o| o.task.id == t.task.id))
+            .map(|t| t.task.id)
+            .collect();
+        delete_tasks(&removed, db_conn)?;
+        for snapshot in &to {
+            restore_task(&snapshot.task, db_conn)?;
+            if let Some(tags) = &snapshot.tags {
+                set_tags_for_task(snapshot.task.id, tags, db_conn)?;
+            }
+        }
+        diesel::update(journal.find(entry.id))
+            .set(undone.eq(undo))
+            .execute(db_conn)?;
+        Ok(())
+    })?;
+    let tasks = if to.is_empty() { from } else { to };
+    Ok(tasks.into_iter().map(|snapshot| snapshot.task).collect())
+}
+
+/// Replaces every tag on the task with the given ones
+fn set_tags_for_task(
+    task: i32,
+    names: &[String],
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::task_tags::dsl::{task_id, task_tags};
+
+    diesel::delete(task_tags.filter(task_id.eq(task))).execute(db_conn)?;
+    add_tags_to_task(task, names, db_conn)
+}
+
+/// Puts a task back exactly as it was, unless another task has taken its ticket
+/// since
+fn restore_task(task: &Task, db_conn: &SqliteConnection) -> Result<(), SuaideError> {
+    use crate::schema::suaide::dsl::{id, suaide, ticket};
+
+    if let Some(task_ticket) = &task.ticket {
+        let taken: i64 = suaide
+            .filter(ticket.eq(task_ticket))
+            .filter(id.ne(task.id))
+            .count()
+            .get_result(db_conn)?;
+        if taken > 0 {
+            return Err(SuaideError::TicketTakenError(task_ticket.clone()));
+        }
+    }
+    let updated = diesel::update(suaide.find(task.id))
+        .set(task)
+        .execute(db_conn)?;
+    if updated == 0 {
+        diesel::insert_into(suaide).values(task).execute(db_conn)?;
+    }
+    Ok(())
+}
+
+/// Gathers the project, tags, notes, subtask progress and unfinished
+/// dependencies of each task into its output representation
+pub(crate) fn get_task_outputs(
+    tasks: &[Task],
+    db_conn: &SqliteConnection,
+) -> Result<Vec<TaskOutput>, SuaideError> {
+    let projects: HashMap<i32, String> = get_projects(db_conn)?
+        .into_iter()
+        .map(|p| (p.id, p.name))
+        .collect();
+    tasks
+        .iter()
+        .map(|task| {
+            let project = task.project_id.and_then(|id| projects.get(&id).cloned());
+            let tags = get_tags_for_task(task.id, db_conn)?;
+            let notes = get_notes_for_task(task.id, db_conn)?
+                .iter()
+                .map(NoteOutput::from)
+                .collect();
+            let subtasks = get_subtask_progress(task.id, db_conn)?;
+            let blocked_by = get_dependencies(task.id, db_conn)?
+                .iter()
+                .filter(|t| !t.is_done())
+                .map(TaskOutput::reference)
+                .collect();
+            Ok(TaskOutput::new(
+                task, project, tags, notes, subtasks, blocked_by,
+            ))
+        })
+        .collect()
+}
+
+pub(crate) fn get_subtasks(
+    task_id: i32,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<Task>, SuaideError> {
+    use crate::schema::suaide::dsl::*;
+
+    Ok(suaide
+        .filter(parent_id.eq(Some(task_id)))
+        .filter(deleted_at.is_null())
+        .load::<Task>(db_conn)?)
+}
+
+/// `None` when the task doesn't have any subtasks
+pub(crate) fn get_subtask_progress(
+    task_id: i32,
+    db_conn: &SqliteConnection,
+) -> Result<Option<SubtaskProgress>, SuaideError> {
+    let subtasks = get_subtasks(task_id, db_conn)?;
+    if subtasks.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(SubtaskProgress {
+        done: subtasks.iter().filter(|t| t.is_done()).count(),
+        total: subtasks.len(),
+    }))
+}
+
+pub(crate) fn get_task_output(
+    task: &Task,
+    db_conn: &SqliteConnection,
+) -> Result<TaskOutput, SuaideError> {
+    let mut output = get_task_outputs(std::slice::from_ref(task), db_conn)?;
+    Ok(output.pop().expect("a single task was provided"))
+}
+
+pub(crate) fn get_tags_for_task(
+    task_id: i32,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<String>, SuaideError> {
+    use crate::schema::{tags, task_tags};
+
+    Ok(task_tags::table
+        .inner_join(tags::table)
+        .filter(task_tags::task_id.eq(task_id))
+        .select(tags::name)
+        .order_by(tags::name.asc())
+        .load::<String>(db_conn)?)
+}
+
+/// Notes are returned oldest first, so they read as a log of progress
+pub(crate) fn get_notes_for_task(
+    task_id: i32,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<Note>, SuaideError> {
+    use crate::schema::notes;
+
+    Ok(notes::table
+        .filter(notes::task_id.eq(task_id))
+        .order_by((notes::created.asc(), notes::id.asc()))
+        .load::<Note>(db_conn)?)
+}
+
+pub(crate) fn add_note_to_task(
+    task_id: i32,
+    body: String,
+    created: i64,
+    db_conn: &SqliteConnection,
+) -> Result<Note, SuaideError> {
+    use crate::schema::notes;
+
+    diesel::insert_into(notes::table)
+        .values(AddNote {
+            task_id,
+            body,
+            created,
+        })
+        .execute(db_conn)?;
+    Ok(notes::table.order_by(notes::id.desc()).first(db_conn)?)
+}
+
+/// The tasks that `task_id` depends on
+pub(crate) fn get_dependencies(
+    task_id: i32,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<Task>, SuaideError> {
+    use crate::schema::{suaide, task_dependencies};
+
+    Ok(task_dependencies::table
+        .inner_join(suaide::table)
+        .filter(task_dependencies::task_id.eq(task_id))
+        .filter(suaide::deleted_at.is_null())
+        .select(suaide::all_columns)
+        .order_by(suaide::id.asc())
+        .load::<Task>(db_conn)?)
+}
+
+/// Records that `task_id` depends on `depends_on`, refusing anything that would
+/// make a task (indirectly) depend on itself
+pub(crate) fn add_dependency(
+    task_id: i32,
+    depends_on: i32,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::task_dependencies;
+
+    let mut visited = HashSet::new();
+    let mut stack = vec![depends_on];
+    while let Some(current) = stack.pop() {
+        if current == task_id {
+            return Err(SuaideError::DependencyCycleError);
+        }
+        if visited.insert(current) {
+            stack.extend(
+                task_dependencies::table
+                    .filter(task_dependencies::task_id.eq(current))
+                    .select(task_dependencies::depends_on)
+                    .load::<i32>(db_conn)?,
+            );
+        }
+    }
+
+    diesel::replace_into(task_dependencies::table)
+        .values(&AddDependency {
+            task_id,
+            depends_on,
+        })
+        .execute(db_conn)?;
+    Ok(())
+}
+
+pub(crate) fn remove_dependency(
+    task_id: i32,
+    depends_on: i32,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::task_dependencies;
+
+    let removed = diesel::delete(
+        task_dependencies::table
+            .filter(task_dependencies::task_id.eq(task_id))
+            .filter(task_dependencies::depends_on.eq(depends_on)),
+    )
+    .execute(db_conn)?;
+    if removed == 0 {
+        return Err(SuaideError::NotFound);
+    }
+    Ok(())
+}
+
+/// The timer that's currently running, there's only ever one at a time
+pub(crate) fn get_running_time_entry(
+    db_conn: &SqliteConnection,
+) -> Result<Option<TimeEntry>, SuaideError> {
+    use crate::schema::time_entries::dsl::*;
+
+    Ok(time_entries
+        .filter(stopped.is_null())
+        .first::<TimeEntry>(db_conn)
+        .optional()?)
+}
+
+pub(crate) fn start_time_entry(
+    task: i32,
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::time_entries;
+
+    diesel::insert_into(time_entries::table)
+        .values(AddTimeEntry {
+            task_id: task,
+            started: now,
+        })
+        .execute(db_conn)?;
+    Ok(())
+}
+
+pub(crate) fn stop_time_entry(
+    entry: &TimeEntry,
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::time_entries::dsl::*;
+
+    diesel::update(time_entries.find(entry.id))
+        .set(stopped.eq(Some(now)))
+        .execute(db_conn)?;
+    Ok(())
+}
+
+/// Every entry that overlaps the window between `start` and `end`
+pub(crate) fn get_time_entries_between(
+    start: i64,
+    end: i64,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<TimeEntry>, SuaideError> {
+    use crate::schema::suaide;
+    use crate::schema::time_entries::dsl::*;
+
+    let entries = time_entries
+        .filter(started.le(end))
+        .filter(stopped.is_null().or(stopped.ge(start)))
+        .order_by(started.asc())
+        .load::<TimeEntry>(db_conn)?;
+
+    // Time logged against tasks in the trash isn't counted
+    let ids: Vec<i32> = entries.iter().map(|entry| entry.task_id).collect();
+    let query = suaide::table.filter(suaide::id.eq_any(ids)).into_boxed();
+    let live: HashSet<i32> = apply_deleted_filter(query)
+        .load::<Task>(db_conn)?
+        .into_iter()
+        .map(|task| task.id)
+        .collect();
+    Ok(entries
+        .into_iter()
+        .filter(|entry| live.contains(&entry.task_id))
+        .collect())
+}
+
+pub(crate) fn add_tags_to_task(
+    task_id: i32,
+    names: &[String],
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::task_tags;
+
+    for tag in get_or_create_tags(names, db_conn)? {
+        let link = AddTaskTag {
+            task_id,
+            tag_id: tag.id,
+        };
+        diesel::replace_into(task_tags::table)
+            .values(&link)
+            .execute(db_conn)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn remove_tags_from_task(
+    task_id: i32,
+    names: &[String],
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::{tags, task_tags};
+
+    let names: Vec<String> = names.iter().map(|n| Tag::normalize(n)).collect();
+    let tag_ids = tags::table
+        .filter(tags::name.eq_any(names))
+        .select(tags::id);
+    diesel::delete(
+        task_tags::table
+            .filter(task_tags::task_id.eq(task_id))
+            .filter(task_tags::tag_id.eq_any(tag_ids)),
+    )
+    .execute(db_conn)?;
+    Ok(())
+}
+
+/// Restricts a task query to tasks carrying at least one of the `include` tags
+/// and none of the `exclude` tags. Empty lists leave the query untouched.
+pub(crate) fn apply_tag_filters<'a>(
+    query: BoxedQuery<'a, Sqlite>,
+    include: &[String],
+    exclude: &[String],
+) -> BoxedQuery<'a, Sqlite> {
+    use crate::schema::suaide::dsl::id;
+    use crate::schema::{tags, task_tags};
+
+    let mut query = query;
+    if !include.is_empty() {
+        let names: Vec<String> = include.iter().map(|n| Tag::normalize(n)).collect();
+        query = query.filter(
+            id.eq_any(
+                task_tags::table
+                    .inner_join(tags::table)
+                    .filter(tags::name.eq_any(names))
+                    .select(task_tags::task_id),
+            ),
+        );
+    }
+    if !exclude.is_empty() {
+        let names: Vec<String> = exclude.iter().map(|n| Tag::normalize(n)).collect();
+        query = query.filter(
+            id.ne_all(
+                task_tags::table
+                    .inner_join(tags::table)
+                    .filter(tags::name.eq_any(names))
+                    .select(task_tags::task_id),
+            ),
+        );
+    }
+    query
+}
+
+/// Restricts a task query to the given statuses, an empty list leaves the query untouched
+pub(crate) fn apply_status_filter<'a>(
+    query: BoxedQuery<'a, Sqlite>,
+    statuses: &[Status],
+) -> BoxedQuery<'a, Sqlite> {
+    use crate::schema::suaide::dsl::status;
+
+    if statuses.is_empty() {
+        return query;
+    }
+    let statuses: Vec<i16> = statuses.iter().map(|s| *s as i16).collect();
+    query.filter(status.eq_any(statuses))
+}
+
+/// Restricts a task query to tasks whose dependencies are all done
+pub(crate) fn apply_ready_filter<'a>(
+    query: BoxedQuery<'a, Sqlite>,
+    db_conn: &SqliteConnection,
+) -> Result<BoxedQuery<'a, Sqlite>, SuaideError> {
+    use crate::schema::{suaide, task_dependencies};
+
+    let done: Vec<i16> = Status::from_filter("done")
+        .iter()
+        .map(|s| *s as i16)
+        .collect();
+    // Diesel won't let the task table appear in a sub-select of itself
+    let waiting: Vec<i32> = task_dependencies::table
+        .inner_join(suaide::table)
+        .filter(suaide::status.ne_all(done))
+        .filter(suaide::deleted_at.is_null())
+        .select(task_dependencies::task_id)
+        .load(db_conn)?;
+    Ok(query.filter(suaide::id.ne_all(waiting)))
+}
+
+/// Finds every task whose ticket, description or notes match an FTS5 query,
+/// best match first. Matches on the ticket count for the most and on notes for
+/// the least
+pub(crate) fn search_tasks(
+    query: &str,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<SearchHit>, SuaideError> {
+    use diesel::result::Error;
+    use diesel::sql_types::Text;
+
+    // FTS5 reports a malformed query as a generic database error, so it's
+    // told apart by its message
+    const QUERY_ERRORS: [&str; 4] = [
+        "fts5: ",
+        "unterminated string",
+        "no such column",
+        "unknown special query",
+    ];
+
+    diesel::sql_query(
+        "SELECT rowid AS task_id, bm25(task_search, 4.0, 2.0, 1.0) AS rank, \
+         snippet(task_search, -1, ?, ?, '...', 12) AS snippet \
+         FROM task_search WHERE task_search MATCH ? ORDER BY rank",
+    )
+    .bind::<Text, _>(SearchHit::HIGHLIGHT_START)
+    .bind::<Text, _>(SearchHit::HIGHLIGHT_END)
+    .bind::<Text, _>(query)
+    .load::<SearchHit>(db_conn)
+    .map_err(|e| match e {
+        Error::DatabaseError(_, info)
+            if QUERY_ERRORS
+                .iter()
+                .any(|error| info.message().starts_with(error)) =>
+        {
+            SuaideError::SearchQueryError(info.message().to_string())
+        }
+        e => SuaideError::from(e),
+    })
+}
+
+/// Starts a task query over the tasks worked on between `start` and `end`, or
+/// due between them when `by_due_date` is set
+pub(crate) fn window_query<'a>(start: i64, end: i64, by_due_date: bool) -> BoxedQuery<'a, Sqlite> {
+    use crate::schema::suaide::dsl::{closed, due, opened, suaide};
+
+    if by_due_date {
+        return suaide.into_boxed().filter(due.between(start, end));
+    }
+    suaide
+        .into_boxed()
+        .filter(opened.between(start, end))
+        .or_filter(closed.between(start, end))
+}
+
+/// Leaves tasks that are in the trash out of a task query, this must be applied
+/// after any `or_filter` so it covers the whole query
+pub(crate) fn apply_deleted_filter(query: BoxedQuery<'_, Sqlite>) -> BoxedQuery<'_, Sqlite> {
+    use crate::schema::suaide::dsl::deleted_at;
+
+    query.filter(deleted_at.is_null())
+}
+
+/// Restricts a task query to a single project, `None` leaves the query untouched
+pub(crate) fn apply_project_filter(
+    query: BoxedQuery<'_, Sqlite>,
+    project: Option<i32>,
+) -> BoxedQuery<'_, Sqlite> {
+    use crate::schema::suaide::dsl::project_id;
+
+    match project {
+        Some(project) => query.filter(project_id.eq(project)),
+        None => query,
+    }
+}
+
+pub(crate) fn get_projects(db_conn: &SqliteConnection) -> Result<Vec<Project>, SuaideError> {
+    use crate::schema::projects::dsl::*;
+
+    Ok(projects.order_by(name.asc()).load::<Project>(db_conn)?)
+}
+
+pub(crate) fn get_project(
+    project_name: &str,
+    db_conn: &SqliteConnection,
+) -> Result<Project, SuaideError> {
+    use crate::schema::projects::dsl::*;
+
+    projects
+        .filter(name.eq(project_name))
+        .first::<Project>(db_conn)
+        .optional()?
+        .ok_or(SuaideError::ProjectNotFound)
+}
+
+pub(crate) fn get_active_project(
+    db_conn: &SqliteConnection,
+) -> Result<Option<Project>, SuaideError> {
+    use crate::schema::projects::dsl::*;
+
+    Ok(projects
+        .filter(active.eq(true))
+        .first::<Project>(db_conn)
+        .optional()?)
+}
+
+pub(crate) fn add_project(
+    project: &AddProject,
+    db_conn: &SqliteConnection,
+) -> Result<Project, SuaideError> {
+    use crate::schema::projects::dsl::projects;
+
+    match diesel::insert_into(projects)
+        .values(project)
+        .execute(db_conn)
+    {
+        Err(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            _,
+        )) => Err(SuaideError::ProjectAlreadyExistsError),
+        Err(e) => Err(SuaideError::from(e)),
+        Ok(_) => get_project(&project.name, db_conn),
+    }
+}
+
+/// Marks the given project as the active one, `None` clears the active project
+pub(crate) fn set_active_project(
+    project: Option<i32>,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    use crate::schema::projects::dsl::*;
+
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        diesel::update(projects.filter(active.eq(true)))
+            .set(active.eq(false))
+            .execute(db_conn)?;
+        if let Some(project) = project {
+            diesel::update(projects.find(project))
+                .set(active.eq(true))
+                .execute(db_conn)?;
+        }
+        Ok(())
+    })
+}
+
+fn get_or_create_tags(
+    names: &[String],
+    db_conn: &SqliteConnection,
+) -> Result<Vec<Tag>, SuaideError> {
+    use crate::schema::tags::dsl::{name, tags};
+
+    let mut result = Vec::with_capacity(names.len());
+    for tag_name in names.iter().map(|n| Tag::normalize(n)) {
+        if tag_name.is_empty() {
+            continue;
+        }
+        let existing = tags
+            .filter(name.eq(&tag_name))
+            .first::<Tag>(db_conn)
+            .optional()?;
+        let tag = match existing {
+            Some(tag) => tag,
+            None => {
+                diesel::insert_into(tags)
+                    .values(&AddTag {
+                        name: tag_name.clone(),
+                    })
+                    .execute(db_conn)?;
+                tags.filter(name.eq(&tag_name)).first::<Tag>(db_conn)?
+            }
+        };
+        result.push(tag);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test_tag_filters {
+    use super::*;
+
+    use crate::common::testing;
+    use crate::database::establish_connection;
+
+    fn filtered_ids(
+        include: &[String],
+        exclude: &[String],
+        db_conn: &SqliteConnection,
+    ) -> Vec<i32> {
+        use crate::schema::suaide::dsl::{id, suaide};
+
+        apply_tag_filters(suaide.into_boxed(), include, exclude)
+            .select(id)
+            .order_by(id.asc())
+            .load::<i32>(db_conn)
+            .expect("This should return an Ok")
+    }
+
+    #[test]
+    fn filters_by_included_and_excluded_tags() {
+        let db_conn = establish_connection("").unwrap();
+        testing::task().insert(&db_conn);
+        testing::task().insert(&db_conn);
+        testing::task().insert(&db_conn);
+
+        add_tags_to_task(1, &["backend".to_string()], &db_conn).unwrap();
+        add_tags_to_task(2, &["backend".to_string(), "oncall".to_string()], &db_conn).unwrap();
+
+        assert_eq!(filtered_ids(&[], &[], &db_conn), vec![1, 2, 3]);
+        assert_eq!(
+            filtered_ids(&["Backend".to_string()], &[], &db_conn),
+            vec![1, 2]
+        );
+        assert_eq!(
+            filtered_ids(&[], &["oncall".to_string()], &db_conn),
+            vec![1, 3]
+        );
+        assert_eq!(
+            filtered_ids(&["backend".to_string()], &["oncall".to_string()], &db_conn),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn removes_tags_from_task() {
+        let db_conn = establish_connection("").unwrap();
+        testing::task().insert(&db_conn);
+
+        add_tags_to_task(1, &["backend".to_string(), "review".to_string()], &db_conn).unwrap();
+        remove_tags_from_task(1, &["REVIEW".to_string()], &db_conn).unwrap();
+
+        assert_eq!(
+            get_tags_for_task(1, &db_conn).unwrap(),
+            vec!["backend".to_string()]
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_status_filter {
+    use super::*;
+
+    use crate::common::testing;
+    use crate::database::establish_connection;
+
+    fn filtered_ids(statuses: &[Status], db_conn: &SqliteConnection) -> Vec<i32> {
+        use crate::schema::suaide::dsl::{id, suaide};
+
+        apply_status_filter(suaide.into_boxed(), statuses)
+            .select(id)
+            .order_by(id.asc())
+            .load::<i32>(db_conn)
+            .expect("This should return an Ok")
+    }
+
+    #[test]
+    fn filters_by_status_groups() {
+        let db_conn = establish_connection("").unwrap();
+        testing::task().insert(&db_conn);
+        testing::task().status(Status::InProgress).insert(&db_conn);
+        testing::task().status(Status::Closed).insert(&db_conn);
+        testing::task().status(Status::Cancelled).insert(&db_conn);
+
+        assert_eq!(filtered_ids(&[], &db_conn), vec![1, 2, 3, 4]);
+        assert_eq!(
+            filtered_ids(&Status::from_filter("open"), &db_conn),
+            vec![1]
+        );
+        assert_eq!(
+            filtered_ids(&Status::from_filter("active"), &db_conn),
+            vec![1, 2]
+        );
+        assert_eq!(
+            filtered_ids(&Status::from_filter("done"), &db_conn),
+            vec![3, 4]
+        );
+        assert_eq!(
+            filtered_ids(&Status::from_filter("all"), &db_conn),
+            vec![1, 2, 3, 4]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use diesel::prelude::*;
+use std::env;
+use std::process::Command;
+
+use crate::domain::{AddTask, Priority, Status};
+
+/// The local time zone is only read from `TZ` when the process starts, so a
+/// test that depends on it reruns itself in a child process with `TZ` set.
+///
+/// Returns true when it's already running in that time zone and the test
+/// should carry on, otherwise it asserts the child run passed
+pub(crate) fn in_time_zone(module: &str, test: &str, tz: &str) -> bool {
+    if env::var("TZ").as_deref() == Ok(tz) {
+        return true;
+    }
+
+    // Test names don't include the crate
+    let name = match module.split_once("::") {
+        Some((_, module)) => format!("{}::{}", module, test),
+        None => test.to_string(),
+    };
+    let output = Command::new(env::current_exe().expect("should find the test binary"))
+        .args([name.as_str(), "--exact", "--test-threads=1"])
+        .env("TZ", tz)
+        .output()
+        .expect("should be able to rerun the test");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success() && stdout.contains("1 passed"),
+        "{} failed in {}:\n{}",
+        name,
+        tz,
+        stdout
+    );
+    false
+}
+
+/// A task for tests to insert, it starts out open with a test description so
+/// only what a test cares about needs to be set
+pub(crate) struct TaskFixture {
+    task: AddTask,
+    deleted_at: Option<i64>,
+}
+
+pub(crate) fn task() -> TaskFixture {
+    TaskFixture {
+        task: AddTask {
+            description: "Test Description".to_string(),
+            opened: 10000,
+            ..AddTask::default()
+        },
+        deleted_at: None,
+    }
+}
+
+impl TaskFixture {
+    pub fn ticket(mut self, ticket: &str) -> Self {
+        self.task.ticket = Some(ticket.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.task.description = description.to_string();
+        self
+    }
+
+    pub fn status(mut self, status: Status) -> Self {
+        self.task.status = status as i16;
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.task.priority = Some(priority as i16);
+        self
+    }
+
+    pub fn blocked(mut self, reason: &str) -> Self {
+        self.task.status = Status::Blocked as i16;
+        self.task.blocked_reason = Some(reason.to_string());
+        self
+    }
+
+    pub fn opened(mut self, opened: i64) -> Self {
+        self.task.opened = opened;
+        self
+    }
+
+    pub fn due(mut self, due: i64) -> Self {
+        self.task.due = Some(due);
+        self
+    }
+
+    pub fn parent(mut self, parent: i32) -> Self {
+        self.task.parent_id = Some(parent);
+        self
+    }
+
+    pub fn closed(mut self, closed: i64) -> Self {
+        self.task.status = Status::Closed as i16;
+        self.task.closed = Some(closed);
+        self
+    }
+
+    /// Moves the task to the trash at the given time
+    pub fn deleted_at(mut self, deleted_at: i64) -> Self {
+        self.deleted_at = Some(deleted_at);
+        self
+    }
+
+    pub fn insert(self, db_conn: &SqliteConnection) {
+        use crate::schema::suaide::dsl;
+
+        diesel::insert_into(dsl::suaide)
+            .values(self.task)
+            .execute(db_conn)
+            .expect("Insert should be successful");
+        if let Some(deleted_at) = self.deleted_at {
+            let id = dsl::suaide
+                .select(dsl::id)
+                .order_by(dsl::id.desc())
+                .first::<i32>(db_conn)
+                .expect("Task should have been inserted");
+            diesel::update(dsl::suaide.find(id))
+                .set(dsl::deleted_at.eq(Some(deleted_at)))
+                .execute(db_conn)
+                .expect("Update should be successful");
+        }
+    }
+}
@@ -1,23 +1,108 @@
 use chrono::prelude::*;
 use chrono::Duration;
+use std::iter;
 
 use crate::{
     common::{DATE_INPUT_LONG, DATE_INPUT_SHORT},
     domain::{SuaideError, Timeframe},
 };
 
+pub(crate) fn timestamp_to_local(timestamp: i64) -> DateTime<Local> {
+    Local.timestamp(timestamp, 0)
+}
+
+/// Parses a date in either the `YYYY-MM-DD` or `DD MMM YYYY` format
+pub(crate) fn parse_date(date: &str) -> Result<NaiveDate, SuaideError> {
+    match NaiveDate::parse_from_str(date, DATE_INPUT_SHORT) {
+        Ok(r) => Ok(r),
+        Err(_) => Ok(NaiveDate::parse_from_str(date, DATE_INPUT_LONG)?),
+    }
+}
+
+/// Parses a due date, on top of the formats accepted by `parse_date` this
+/// accepts `today`, `tomorrow`, a day of the week such as `friday` _(the next
+/// one, including today)_ and offsets such as `+3d` or `+2w`. Tasks are due by
+/// the end of the given day
+pub(crate) fn parse_due_date(input: &str, today: Date<Local>) -> Result<i64, SuaideError> {
+    let input = input.trim().to_lowercase();
+    let today = today.naive_local();
+    let date = match input.as_str() {
+        "today" => today,
+        "tomorrow" => today.succ(),
+        _ => {
+            if let Some(date) = parse_offset(&input).and_then(|o| today.checked_add_signed(o)) {
+                date
+            } else if let Ok(weekday) = input.parse::<Weekday>() {
+                let days_ahead = (7 + weekday.num_days_from_monday()
+                    - today.weekday().num_days_from_monday())
+                    % 7;
+                today + Duration::days(i64::from(days_ahead))
+            } else {
+                parse_date(&input)?
+            }
+        }
+    };
+    end_of_day(date)
+}
+
+/// Due dates are stored as the last second of the day they're due
+pub(crate) fn end_of_day(date: NaiveDate) -> Result<i64, SuaideError> {
+    local_datetime(date.and_hms(23, 59, 59))
+        .map(|time| time.timestamp())
+        .ok_or_else(|| SuaideError::LocalTimeError(date.to_string()))
+}
+
+/// The local time for a date and time of day. When the clocks go back the
+/// earlier of the two times is used, and when they go forward over it it's the
+/// first time that exists after it
+pub(crate) fn local_datetime(local: NaiveDateTime) -> Option<DateTime<Local>> {
+    let minute = local.with_second(0)?;
+    let after =
+        (1..=24 * 60).filter_map(|minutes| minute.checked_add_signed(Duration::minutes(minutes)));
+    iter::once(local).chain(after).find_map(|candidate| {
+        // Times that don't exist can come back shifted rather than as `None`,
+        // so only those that round trip are used
+        Local
+            .from_local_datetime(&candidate)
+            .earliest()
+            .filter(|time| time.naive_local() == candidate)
+    })
+}
+
+fn parse_offset(input: &str) -> Option<Duration> {
+    let input = input.strip_prefix('+')?;
+    let (idx, unit) = input.char_indices().last()?;
+    let amount = input[..idx].parse::<i64>().ok()?;
+    let days = match unit {
+        'd' => amount,
+        'w' => amount.checked_mul(7)?,
+        _ => return None,
+    };
+    // Durations are held in milliseconds and panic when that would overflow
+    let seconds = days.checked_mul(86_400)?;
+    seconds.checked_mul(1_000)?;
+    Some(Duration::seconds(seconds))
+}
+
+/// Parses a length of time such as `30d` or `2w`
+pub(crate) fn parse_period(input: &str) -> Result<Duration, SuaideError> {
+    let period = input.trim().trim_start_matches('+').to_lowercase();
+    parse_offset(&format!("+{}", period))
+        .ok_or_else(|| SuaideError::PeriodFormatError(input.to_string()))
+}
+
+/// Formats a number of seconds as hours and minutes, e.g. `2h 05m`
+pub(crate) fn format_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
 pub(crate) fn calculate_duration_from_dates(
     from: &str,
     to: &str,
 ) -> Result<(i64, i64), SuaideError> {
-    let from = match NaiveDate::parse_from_str(from, DATE_INPUT_SHORT) {
-        Ok(r) => r,
-        Err(_) => NaiveDate::parse_from_str(from, DATE_INPUT_LONG)?,
-    };
-    let to = match NaiveDate::parse_from_str(to, DATE_INPUT_SHORT) {
-        Ok(r) => r,
-        Err(_) => NaiveDate::parse_from_str(to, DATE_INPUT_LONG)?,
-    };
+    let from = parse_date(from)?;
+    let to = parse_date(to)?;
     let from = Local
         .ymd(from.year(), from.month(), from.day())
         .and_hms(0, 0, 1)
@@ -69,11 +154,51 @@ pub(crate) fn calculate_duration_from_timeframe(
                 .and_hms(0, 0, 1),
             base_hms,
         ),
+
+        Timeframe::Overdue => (
+            Local.timestamp(0, 0),
+            base.and_hms(0, 0, 0) - Duration::seconds(1),
+        ),
+
+        Timeframe::Upcoming => (base.and_hms(0, 0, 1), base_hms + Duration::days(7)),
     };
 
     (start.timestamp(), end.timestamp())
 }
 
+/// Finds the most recent working day before `today`, skipping any day that
+/// isn't a working day or is a holiday
+pub(crate) fn previous_working_day(
+    today: Date<Local>,
+    working_days: &[Weekday],
+    holidays: &[NaiveDate],
+) -> Date<Local> {
+    let mut day = today.pred();
+    // Bail out after a year rather than loop forever if nothing is a working day
+    for _ in 0..366 {
+        if working_days.contains(&day.weekday()) && !holidays.contains(&day.naive_local()) {
+            return day;
+        }
+        day = day.pred();
+    }
+    today.pred()
+}
+
+/// The window covered by the "yesterday" section of a stand-up, from the start
+/// of the previous working day up until the end of yesterday, so anything done
+/// over a weekend is still picked up on the Monday
+pub(crate) fn calculate_standup_lookback(
+    today: Date<Local>,
+    working_days: &[Weekday],
+    holidays: &[NaiveDate],
+) -> (i64, i64) {
+    let start = previous_working_day(today, working_days, holidays);
+    (
+        start.and_hms(0, 0, 1).timestamp(),
+        today.pred().and_hms(23, 59, 59).timestamp(),
+    )
+}
+
 #[cfg(test)]
 mod test_from_dates {
     use super::*;
@@ -336,4 +461,204 @@ mod test_from_timeframe {
             (min, max)
         );
     }
+
+    #[test]
+    fn overdue() {
+        let max = Local.ymd(2000, 3, 6).and_hms(23, 59, 59).timestamp();
+        assert_eq!(
+            calculate_duration_from_timeframe(*BASE_DATE, Timeframe::Overdue),
+            (0, max)
+        );
+    }
+
+    #[test]
+    fn upcoming() {
+        let min = Local.ymd(2000, 3, 7).and_hms(0, 0, 1).timestamp();
+        let max = Local.ymd(2000, 3, 14).and_hms(23, 59, 59).timestamp();
+        assert_eq!(
+            calculate_duration_from_timeframe(*BASE_DATE, Timeframe::Upcoming),
+            (min, max)
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_due_dates {
+    use super::*;
+    use lazy_static::lazy_static;
+
+    use crate::common::testing::in_time_zone;
+
+    lazy_static! {
+        // A Wednesday
+        static ref TODAY: Date<Local> = Local.ymd(2020, 10, 7);
+    }
+
+    fn end_of(date: Date<Local>) -> i64 {
+        date.and_hms(23, 59, 59).timestamp()
+    }
+
+    #[test]
+    fn absolute_dates() {
+        let expected = end_of(Local.ymd(2020, 11, 1));
+        assert_eq!(parse_due_date("2020-11-01", *TODAY).unwrap(), expected);
+        assert_eq!(parse_due_date("1 Nov 2020", *TODAY).unwrap(), expected);
+    }
+
+    #[test]
+    fn relative_days() {
+        assert_eq!(parse_due_date("today", *TODAY).unwrap(), end_of(*TODAY));
+        assert_eq!(
+            parse_due_date("Tomorrow", *TODAY).unwrap(),
+            end_of(Local.ymd(2020, 10, 8))
+        );
+    }
+
+    #[test]
+    fn days_of_the_week() {
+        assert_eq!(
+            parse_due_date("friday", *TODAY).unwrap(),
+            end_of(Local.ymd(2020, 10, 9))
+        );
+        assert_eq!(
+            parse_due_date("mon", *TODAY).unwrap(),
+            end_of(Local.ymd(2020, 10, 12))
+        );
+        assert_eq!(parse_due_date("wednesday", *TODAY).unwrap(), end_of(*TODAY));
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(
+            parse_due_date("+3d", *TODAY).unwrap(),
+            end_of(Local.ymd(2020, 10, 10))
+        );
+        assert_eq!(
+            parse_due_date("+2w", *TODAY).unwrap(),
+            end_of(Local.ymd(2020, 10, 21))
+        );
+    }
+
+    #[test]
+    fn skipped_days() {
+        if !in_time_zone(module_path!(), "skipped_days", "Pacific/Apia") {
+            return;
+        }
+        // Samoa skipped the 30th of December 2011 when it crossed the date line,
+        // anything due that day is due as the 31st starts
+        let today = Local.ymd(2011, 12, 29);
+        let expected = Local.ymd(2011, 12, 31).and_hms(0, 0, 0).timestamp();
+        assert_eq!(parse_due_date("2011-12-30", today).unwrap(), expected);
+        assert_eq!(parse_due_date("+1d", today).unwrap(), expected);
+        assert_eq!(parse_due_date("tomorrow", today).unwrap(), expected);
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(parse_due_date("+3x", *TODAY).is_err());
+        assert!(parse_due_date("someday", *TODAY).is_err());
+        assert!(parse_due_date("+", *TODAY).is_err());
+        assert!(parse_due_date("+3é", *TODAY).is_err());
+        assert!(parse_due_date("+99999999999999d", *TODAY).is_err());
+        assert!(parse_due_date("+9999999999d", *TODAY).is_err());
+    }
+
+    #[test]
+    fn periods() {
+        assert_eq!(parse_period("30d").unwrap(), Duration::days(30));
+        assert_eq!(parse_period("+2W").unwrap(), Duration::weeks(2));
+        assert!(parse_period("30").is_err());
+        assert!(parse_period("d").is_err());
+        assert!(parse_period("30é").is_err());
+        assert!(parse_period("99999999999999d").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_working_days {
+    use super::*;
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        static ref WEEKDAYS: Vec<Weekday> = vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ];
+    }
+
+    #[test]
+    fn tuesday_looks_back_to_monday() {
+        let today = Local.ymd(2020, 10, 6);
+        assert_eq!(
+            previous_working_day(today, &WEEKDAYS, &[]),
+            Local.ymd(2020, 10, 5)
+        );
+    }
+
+    #[test]
+    fn monday_looks_back_to_friday() {
+        let today = Local.ymd(2020, 10, 5);
+        assert_eq!(
+            previous_working_day(today, &WEEKDAYS, &[]),
+            Local.ymd(2020, 10, 2)
+        );
+    }
+
+    #[test]
+    fn sunday_looks_back_to_friday() {
+        let today = Local.ymd(2020, 10, 4);
+        assert_eq!(
+            previous_working_day(today, &WEEKDAYS, &[]),
+            Local.ymd(2020, 10, 2)
+        );
+    }
+
+    #[test]
+    fn skips_holidays() {
+        let today = Local.ymd(2020, 10, 5);
+        let holidays = vec![NaiveDate::from_ymd(2020, 10, 2)];
+        assert_eq!(
+            previous_working_day(today, &WEEKDAYS, &holidays),
+            Local.ymd(2020, 10, 1)
+        );
+    }
+
+    #[test]
+    fn custom_working_days() {
+        let working_days = vec![
+            Weekday::Sun,
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+        ];
+        let today = Local.ymd(2020, 10, 4);
+        assert_eq!(
+            previous_working_day(today, &working_days, &[]),
+            Local.ymd(2020, 10, 1)
+        );
+    }
+
+    #[test]
+    fn no_working_days_falls_back_to_yesterday() {
+        let today = Local.ymd(2020, 10, 5);
+        assert_eq!(
+            previous_working_day(today, &[], &[]),
+            Local.ymd(2020, 10, 4)
+        );
+    }
+
+    #[test]
+    fn lookback_covers_the_weekend() {
+        let today = Local.ymd(2020, 10, 5);
+        let min = Local.ymd(2020, 10, 2).and_hms(0, 0, 1).timestamp();
+        let max = Local.ymd(2020, 10, 4).and_hms(23, 59, 59).timestamp();
+        assert_eq!(
+            calculate_standup_lookback(today, &WEEKDAYS, &[]),
+            (min, max)
+        );
+    }
 }
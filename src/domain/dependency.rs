@@ -0,0 +1,11 @@
+use diesel::Insertable;
+
+use crate::schema::task_dependencies;
+
+/// `task_id` can't be worked on until `depends_on` is done
+#[derive(Insertable)]
+#[table_name = "task_dependencies"]
+pub(crate) struct AddDependency {
+    pub(crate) task_id: i32,
+    pub(crate) depends_on: i32,
+}
@@ -14,18 +14,87 @@ pub enum SuaideError {
     #[error("Ticket already exists, ticket id must be unique")]
     TicketAlreadyExistsError,
 
+    #[error("Ticket {0} is in the trash, restore it or empty the trash first")]
+    TicketInTrashError(String),
+
+    #[error("A reason is required when marking a task as blocked")]
+    BlockedReasonRequired,
+
+    #[error("Task has {0} open subtask(s), use --force to close it anyway")]
+    OpenSubtasksError(usize),
+
+    #[error("This would change {0} task(s), use --yes to confirm")]
+    ConfirmationRequired(usize),
+
+    #[error("Linking these tasks would create a dependency cycle")]
+    DependencyCycleError,
+
+    #[error("A timer is already running for {0}, stop it first")]
+    TimerAlreadyRunningError(String),
+
+    #[error("There isn't a timer running")]
+    NoTimerRunningError,
+
+    #[error("Ticket {0} has since been given to another task, so it can't be put back")]
+    TicketTakenError(String),
+
+    #[error("There's nothing to undo")]
+    NothingToUndoError,
+
+    #[error("There's nothing to redo")]
+    NothingToRedoError,
+
+    #[error("Row {0} can't be imported: {1}")]
+    ImportRowError(usize, String),
+
+    #[error("Task {0} already exists, use --on-conflict skip or update to import anyway")]
+    ImportConflictError(String),
+
+    #[error("Invalid search query: {0}")]
+    SearchQueryError(String),
+
+    #[error("Project not found")]
+    ProjectNotFound,
+
+    #[error("Project already exists, project name must be unique")]
+    ProjectAlreadyExistsError,
+
     #[error(transparent)]
     ConnectionError(#[from] diesel::result::ConnectionError),
 
     #[error(transparent)]
     StorageError(#[from] diesel::result::Error),
 
+    #[error("Invalid working day {0}, expected a day of the week such as mon or monday")]
+    WorkingDayError(String),
+
+    #[error("Invalid recurrence {0}, expected daily, weekdays, weekly:mon,thu or monthly:15")]
+    RecurrenceFormatError(String),
+
+    #[error("Task doesn't repeat")]
+    RecurrenceNotFound,
+
+    #[error("Invalid status {0}, expected open, in-progress, blocked, closed or cancelled")]
+    StatusFormatError(String),
+
+    #[error("Invalid priority {0}, expected p0, p1, p2 or p3")]
+    PriorityFormatError(String),
+
+    #[error("Invalid period {0}, expected a number of days or weeks such as 30d or 2w")]
+    PeriodFormatError(String),
+
+    #[error("The end of {0} doesn't exist in the local time zone")]
+    LocalTimeError(String),
+
     #[error("Expected date in either format DD MMM YYYY or YYYY-MM-DD")]
     DateFormatError(#[from] chrono::ParseError),
 
     #[error(transparent)]
     InputError(#[from] std::io::Error),
 
+    #[error(transparent)]
+    SerializationError(#[from] serde_json::Error),
+
     #[error(transparent)]
     MigrationError(#[from] diesel_migrations::RunMigrationsError),
 
@@ -0,0 +1,89 @@
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::common::time::timestamp_to_local;
+use crate::domain::{Priority, Status, Task};
+use crate::schema::task_events;
+
+/// A single change to one of a task's fields, values are stored in the same
+/// human readable form they're displayed in apart from statuses, which are
+/// stored by their key so they can be read back whatever they're called
+#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq)]
+pub struct TaskEvent {
+    pub(crate) id: i32,
+    pub(crate) task_id: i32,
+    pub(crate) field: String,
+    pub(crate) old_value: Option<String>,
+    pub(crate) new_value: Option<String>,
+    pub(crate) created: i64,
+}
+
+impl TaskEvent {
+    pub const STATUS: &'static str = "status";
+
+    /// The status a status event moved the task to, older events stored its
+    /// name rather than its key but both parse
+    pub(crate) fn new_status(&self) -> Option<Status> {
+        match self.field.as_str() {
+            TaskEvent::STATUS => self.new_value.as_deref()?.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Insertable, Eq, PartialEq)]
+#[table_name = "task_events"]
+pub(crate) struct AddTaskEvent {
+    pub(crate) task_id: i32,
+    pub(crate) field: String,
+    pub(crate) old_value: Option<String>,
+    pub(crate) new_value: Option<String>,
+    pub(crate) created: i64,
+}
+
+impl AddTaskEvent {
+    pub(crate) fn new(
+        task_id: i32,
+        field: &str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+        created: i64,
+    ) -> Self {
+        Self {
+            task_id,
+            field: field.to_string(),
+            old_value,
+            new_value,
+            created,
+        }
+    }
+
+    /// The first event in a task's history is the status it was added with
+    pub(crate) fn added(task: &Task, now: i64) -> Self {
+        Self::new(
+            task.id,
+            TaskEvent::STATUS,
+            None,
+            Some(format_status(task.status)),
+            now,
+        )
+    }
+}
+
+pub(crate) fn format_status(status: i16) -> String {
+    Status::from(status).key().to_string()
+}
+
+pub(crate) fn format_due(due: i64) -> String {
+    timestamp_to_local(due).format("%Y-%m-%d").to_string()
+}
+
+pub(crate) fn format_time(timestamp: i64) -> String {
+    timestamp_to_local(timestamp)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+pub(crate) fn format_priority(priority: i16) -> String {
+    Priority::from(priority).name().to_string()
+}
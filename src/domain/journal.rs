@@ -0,0 +1,80 @@
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{SuaideError, Task};
+use crate::schema::journal;
+
+/// A mutating command along with snapshots of every task it touched, both
+/// before and after it ran, so it can be undone and redone
+#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq)]
+pub struct JournalEntry {
+    pub(crate) id: i32,
+    pub(crate) command: String,
+    pub(crate) before_tasks: String,
+    pub(crate) after_tasks: String,
+    pub(crate) created: i64,
+    pub(crate) undone: bool,
+}
+
+impl JournalEntry {
+    pub fn before(&self) -> Result<Vec<TaskSnapshot>, SuaideError> {
+        Ok(serde_json::from_str(&self.before_tasks)?)
+    }
+
+    pub fn after(&self) -> Result<Vec<TaskSnapshot>, SuaideError> {
+        Ok(serde_json::from_str(&self.after_tasks)?)
+    }
+}
+
+/// A task as it was journaled, along with its tags when the command changed
+/// them
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct TaskSnapshot {
+    #[serde(flatten)]
+    pub(crate) task: Task,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) tags: Option<Vec<String>>,
+}
+
+impl TaskSnapshot {
+    pub fn with_tags(task: Task, tags: Vec<String>) -> Self {
+        Self {
+            task,
+            tags: Some(tags),
+        }
+    }
+}
+
+impl From<&Task> for TaskSnapshot {
+    fn from(task: &Task) -> Self {
+        Self {
+            task: task.clone(),
+            tags: None,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "journal"]
+pub(crate) struct AddJournalEntry {
+    pub(crate) command: String,
+    pub(crate) before_tasks: String,
+    pub(crate) after_tasks: String,
+    pub(crate) created: i64,
+}
+
+impl AddJournalEntry {
+    pub(crate) fn new(
+        command: &str,
+        before: &[TaskSnapshot],
+        after: &[TaskSnapshot],
+        created: i64,
+    ) -> Result<Self, SuaideError> {
+        Ok(Self {
+            command: command.to_string(),
+            before_tasks: serde_json::to_string(before)?,
+            after_tasks: serde_json::to_string(after)?,
+            created,
+        })
+    }
+}
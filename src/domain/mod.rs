@@ -1,9 +1,40 @@
+mod dependency;
 mod errors;
+mod event;
+mod journal;
+mod note;
+mod output;
+mod priority;
+mod project;
+mod recurrence;
+mod search;
+mod sort;
 mod status;
+mod tag;
 mod task;
+mod taskwarrior;
+mod template;
+mod time_entry;
 mod timeframe;
 
+pub(crate) use dependency::AddDependency;
 pub(crate) use errors::SuaideError;
+pub(crate) use event::{AddTaskEvent, TaskEvent};
+pub(crate) use journal::{AddJournalEntry, JournalEntry, TaskSnapshot};
+pub(crate) use note::{AddNote, Note};
+pub(crate) use output::{
+    ExportRow, NoteOutput, OutputFormat, RecurrenceOutput, SearchResultOutput, SubtaskProgress,
+    TaskEventOutput, TaskOutput,
+};
+pub(crate) use priority::Priority;
+pub(crate) use project::{AddProject, Project};
+pub(crate) use recurrence::{AddRecurrence, Recurrence, RecurrenceRule};
+pub(crate) use search::SearchHit;
+pub(crate) use sort::SortBy;
 pub(crate) use status::Status;
+pub(crate) use tag::{AddTag, AddTaskTag, Tag};
 pub(crate) use task::{AddTask, Task, TaskChangeSet};
+pub(crate) use taskwarrior::TaskwarriorTask;
+pub(crate) use template::{StandupFormat, StandupTemplate};
+pub(crate) use time_entry::{AddTimeEntry, TimeEntry};
 pub(crate) use timeframe::Timeframe;
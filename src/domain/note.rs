@@ -0,0 +1,20 @@
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::notes;
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq)]
+pub struct Note {
+    pub(crate) id: i32,
+    pub(crate) task_id: i32,
+    pub(crate) body: String,
+    pub(crate) created: i64,
+}
+
+#[derive(Insertable)]
+#[table_name = "notes"]
+pub(crate) struct AddNote {
+    pub(crate) task_id: i32,
+    pub(crate) body: String,
+    pub(crate) created: i64,
+}
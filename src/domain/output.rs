@@ -0,0 +1,280 @@
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use std::convert::From;
+
+use crate::common::time::timestamp_to_local;
+use crate::common::DATE_FORMAT;
+use crate::domain::{Note, Priority, Recurrence, SearchHit, Status, Task, TaskEvent};
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Copy, Clone, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl From<&str> for OutputFormat {
+    fn from(s: &str) -> Self {
+        match s {
+            "text" => OutputFormat::Text,
+            "json" => OutputFormat::Json,
+            _ => panic!("unable to convert argument to output format"),
+        }
+    }
+}
+
+/// The stable, machine readable representation of a task
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct TaskOutput {
+    pub(crate) id: i32,
+    pub(crate) ticket: Option<String>,
+    pub(crate) description: String,
+    pub(crate) status: Status,
+    pub(crate) opened: DateTime<Local>,
+    pub(crate) closed: Option<DateTime<Local>>,
+    pub(crate) project: Option<String>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) blocked_reason: Option<String>,
+    pub(crate) due: Option<DateTime<Local>>,
+    pub(crate) priority: Option<Priority>,
+    pub(crate) notes: Vec<NoteOutput>,
+    pub(crate) parent_id: Option<i32>,
+    pub(crate) subtasks: Option<SubtaskProgress>,
+    pub(crate) blocked_by: Vec<String>,
+}
+
+/// How many of a task's subtasks are done, out of all of them
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Copy, Clone)]
+pub struct SubtaskProgress {
+    pub(crate) done: usize,
+    pub(crate) total: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct NoteOutput {
+    pub(crate) body: String,
+    pub(crate) created: DateTime<Local>,
+}
+
+impl From<&Note> for NoteOutput {
+    fn from(note: &Note) -> Self {
+        Self {
+            body: note.body.clone(),
+            created: timestamp_to_local(note.created),
+        }
+    }
+}
+
+/// A single entry in a task's history
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct TaskEventOutput {
+    pub(crate) field: String,
+    pub(crate) old_value: Option<String>,
+    pub(crate) new_value: Option<String>,
+    pub(crate) created: DateTime<Local>,
+}
+
+impl From<&TaskEvent> for TaskEventOutput {
+    fn from(event: &TaskEvent) -> Self {
+        // Statuses are stored by key but shown by name
+        let display = |value: &Option<String>| match event.field.as_str() {
+            TaskEvent::STATUS => value.as_deref().map(|value| match value.parse::<Status>() {
+                Ok(status) => status.name().to_string(),
+                Err(_) => value.to_string(),
+            }),
+            _ => value.clone(),
+        };
+        Self {
+            field: event.field.clone(),
+            old_value: display(&event.old_value),
+            new_value: display(&event.new_value),
+            created: timestamp_to_local(event.created),
+        }
+    }
+}
+
+/// A task as it's exported, each timestamp is given as ISO-8601 in UTC as well
+/// as in local time. The fields are the export's columns so they need to stay
+/// stable
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ExportRow {
+    pub(crate) id: i32,
+    pub(crate) ticket: Option<String>,
+    pub(crate) description: String,
+    pub(crate) status: Status,
+    pub(crate) project: Option<String>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) priority: Option<Priority>,
+    pub(crate) parent_id: Option<i32>,
+    pub(crate) blocked_reason: Option<String>,
+    pub(crate) opened: DateTime<Utc>,
+    pub(crate) opened_local: String,
+    pub(crate) closed: Option<DateTime<Utc>>,
+    pub(crate) closed_local: Option<String>,
+    pub(crate) due: Option<DateTime<Utc>>,
+    pub(crate) due_local: Option<String>,
+}
+
+impl ExportRow {
+    pub const COLUMNS: [&'static str; 15] = [
+        "id",
+        "ticket",
+        "description",
+        "status",
+        "project",
+        "tags",
+        "priority",
+        "parent_id",
+        "blocked_reason",
+        "opened",
+        "opened_local",
+        "closed",
+        "closed_local",
+        "due",
+        "due_local",
+    ];
+
+    /// The row's values in the same order as its columns, tags are comma
+    /// separated and anything missing is left empty
+    pub fn to_record(&self) -> Vec<String> {
+        let iso = |date: &DateTime<Utc>| date.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        vec![
+            self.id.to_string(),
+            self.ticket.clone().unwrap_or_default(),
+            self.description.clone(),
+            self.status.key().to_string(),
+            self.project.clone().unwrap_or_default(),
+            self.tags.join(","),
+            self.priority
+                .map(|p| p.name().to_lowercase())
+                .unwrap_or_default(),
+            self.parent_id.map(|p| p.to_string()).unwrap_or_default(),
+            self.blocked_reason.clone().unwrap_or_default(),
+            iso(&self.opened),
+            self.opened_local.clone(),
+            self.closed.as_ref().map(iso).unwrap_or_default(),
+            self.closed_local.clone().unwrap_or_default(),
+            self.due.as_ref().map(iso).unwrap_or_default(),
+            self.due_local.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl From<&TaskOutput> for ExportRow {
+    fn from(task: &TaskOutput) -> Self {
+        let local = |date: DateTime<Local>| date.format(DATE_FORMAT).to_string();
+        Self {
+            id: task.id,
+            ticket: task.ticket.clone(),
+            description: task.description.clone(),
+            status: task.status,
+            project: task.project.clone(),
+            tags: task.tags.clone(),
+            priority: task.priority,
+            parent_id: task.parent_id,
+            blocked_reason: task.blocked_reason.clone(),
+            opened: task.opened.with_timezone(&Utc),
+            opened_local: local(task.opened),
+            closed: task.closed.map(|d| d.with_timezone(&Utc)),
+            closed_local: task.closed.map(local),
+            due: task.due.map(|d| d.with_timezone(&Utc)),
+            due_local: task.due.map(local),
+        }
+    }
+}
+
+/// A repeating task along with when its next instance is due
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct RecurrenceOutput {
+    pub(crate) task_id: i32,
+    pub(crate) task: String,
+    pub(crate) description: String,
+    pub(crate) rule: String,
+    pub(crate) next_due: DateTime<Local>,
+}
+
+impl RecurrenceOutput {
+    pub fn new(recurrence: &Recurrence, template: &Task) -> Self {
+        Self {
+            task_id: template.id,
+            task: TaskOutput::reference(template),
+            description: template.description.clone(),
+            rule: recurrence.rule.clone(),
+            next_due: timestamp_to_local(recurrence.next_due),
+        }
+    }
+}
+
+/// A task matching a search, `score` is higher the better the task matched
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SearchResultOutput {
+    #[serde(flatten)]
+    pub(crate) task: TaskOutput,
+    pub(crate) score: f64,
+    /// Keeps the markers around matched terms, they're left out of the JSON
+    #[serde(serialize_with = "without_highlights")]
+    pub(crate) snippet: String,
+}
+
+impl SearchResultOutput {
+    pub fn new(task: TaskOutput, hit: &SearchHit) -> Self {
+        Self {
+            task,
+            score: -hit.rank,
+            snippet: hit.snippet.clone(),
+        }
+    }
+}
+
+fn without_highlights<S: Serializer>(snippet: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(
+        &snippet
+            .replace(SearchHit::HIGHLIGHT_START, "")
+            .replace(SearchHit::HIGHLIGHT_END, ""),
+    )
+}
+
+impl TaskOutput {
+    pub fn new(
+        task: &Task,
+        project: Option<String>,
+        tags: Vec<String>,
+        notes: Vec<NoteOutput>,
+        subtasks: Option<SubtaskProgress>,
+        blocked_by: Vec<String>,
+    ) -> Self {
+        Self {
+            id: task.id,
+            ticket: task.ticket.clone(),
+            description: task.description.clone(),
+            status: task.task_status(),
+            opened: timestamp_to_local(task.opened),
+            closed: task.closed.map(timestamp_to_local),
+            project,
+            tags,
+            blocked_reason: task.blocked_reason.clone(),
+            due: task.due.map(timestamp_to_local),
+            priority: task.task_priority(),
+            notes,
+            parent_id: task.parent_id,
+            subtasks,
+            blocked_by,
+        }
+    }
+
+    /// How a task is referred to, its ticket if it has one otherwise `#id`
+    pub fn reference(task: &Task) -> String {
+        match &task.ticket {
+            Some(ticket) => ticket.clone(),
+            None => format!("#{}", task.id),
+        }
+    }
+
+    /// A task is overdue once its due date has passed without it being done
+    pub fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        match self.status {
+            Status::Closed | Status::Cancelled => false,
+            _ => self.due.is_some_and(|due| due < now),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::convert::From;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::domain::SuaideError;
+
+/// How urgent a task is, `P0` being the most urgent
+#[derive(Debug, Serialize, Deserialize, Eq, Ord, PartialOrd, PartialEq, Hash, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    P0,
+    P1,
+    P2,
+    P3,
+}
+
+impl Priority {
+    /// The name of the priority, without any styling
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::P0 => "P0",
+            Priority::P1 => "P1",
+            Priority::P2 => "P2",
+            Priority::P3 => "P3",
+        }
+    }
+}
+
+impl From<i16> for Priority {
+    fn from(i: i16) -> Self {
+        match i {
+            0 => Priority::P0,
+            1 => Priority::P1,
+            2 => Priority::P2,
+            3 => Priority::P3,
+            _ => panic!("Invalid priority"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = SuaideError;
+
+    /// Reads a priority from any of the names accepted as an argument, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "p0" | "critical" => Ok(Priority::P0),
+            "p1" | "high" => Ok(Priority::P1),
+            "p2" | "medium" => Ok(Priority::P2),
+            "p3" | "low" => Ok(Priority::P3),
+            _ => Err(SuaideError::PriorityFormatError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Priority::P0 => self.name().red().bold(),
+            Priority::P1 => self.name().red(),
+            Priority::P2 => self.name().yellow(),
+            Priority::P3 => self.name().normal(),
+        };
+        write!(f, "{}", text)
+    }
+}
@@ -0,0 +1,38 @@
+use colored::Colorize;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::schema::projects;
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq, Clone)]
+pub struct Project {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) prefix: String,
+    pub(crate) active: bool,
+}
+
+#[derive(Insertable)]
+#[table_name = "projects"]
+pub(crate) struct AddProject {
+    pub(crate) name: String,
+    pub(crate) prefix: String,
+}
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.active { "*" } else { " " };
+        if self.prefix.is_empty() {
+            write!(f, "{} {}", marker, self.name.bold())
+        } else {
+            write!(
+                f,
+                "{} {} ({})",
+                marker,
+                self.name.bold(),
+                self.prefix.italic()
+            )
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::prelude::*;
+use chrono::Duration;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::domain::SuaideError;
+use crate::schema::recurrences;
+
+/// Repeats a template task, `next_due` is when the next instance is due and
+/// `last_task_id` is the most recent instance
+#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq)]
+pub struct Recurrence {
+    pub(crate) id: i32,
+    pub(crate) task_id: i32,
+    pub(crate) rule: String,
+    pub(crate) next_due: i64,
+    pub(crate) last_task_id: i32,
+}
+
+#[derive(Insertable)]
+#[table_name = "recurrences"]
+pub(crate) struct AddRecurrence {
+    pub(crate) task_id: i32,
+    pub(crate) rule: String,
+    pub(crate) next_due: i64,
+    pub(crate) last_task_id: i32,
+}
+
+/// How often a task repeats
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum RecurrenceRule {
+    Daily,
+    Weekdays,
+    Weekly(Vec<Weekday>),
+    /// Months without the day repeat on their last day instead
+    Monthly(u32),
+}
+
+impl RecurrenceRule {
+    /// The first day on or after `date` the task is due
+    pub fn first_from(&self, date: Date<Local>) -> Date<Local> {
+        let mut date = date;
+        while !self.falls_on(date) {
+            date = date.succ();
+        }
+        date
+    }
+
+    /// The first day after `date` the task is due
+    pub fn next_after(&self, date: Date<Local>) -> Date<Local> {
+        self.first_from(date.succ())
+    }
+
+    fn falls_on(&self, date: Date<Local>) -> bool {
+        match self {
+            RecurrenceRule::Daily => true,
+            RecurrenceRule::Weekdays => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+            RecurrenceRule::Weekly(days) => days.contains(&date.weekday()),
+            RecurrenceRule::Monthly(day) => {
+                let last_day = (date.with_day(1).unwrap() + Duration::days(32))
+                    .with_day(1)
+                    .unwrap()
+                    .pred()
+                    .day();
+                date.day() == (*day).min(last_day)
+            }
+        }
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = SuaideError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim().to_lowercase();
+        let (kind, on) = match input.split_once(':') {
+            Some((kind, on)) => (kind, Some(on)),
+            None => (input.as_str(), None),
+        };
+        let error = || SuaideError::RecurrenceFormatError(s.to_string());
+        match (kind, on) {
+            ("daily", None) => Ok(RecurrenceRule::Daily),
+            ("weekdays", None) => Ok(RecurrenceRule::Weekdays),
+            ("weekly", Some(on)) => {
+                let mut days = on
+                    .split(',')
+                    .map(|day| day.trim().parse::<Weekday>().map_err(|_| error()))
+                    .collect::<Result<Vec<Weekday>, SuaideError>>()?;
+                days.sort_by_key(|day| day.num_days_from_monday());
+                days.dedup();
+                Ok(RecurrenceRule::Weekly(days))
+            }
+            ("monthly", Some(on)) => match on.trim().parse::<u32>() {
+                Ok(day) if (1..=31).contains(&day) => Ok(RecurrenceRule::Monthly(day)),
+                _ => Err(error()),
+            },
+            _ => Err(error()),
+        }
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecurrenceRule::Daily => write!(f, "daily"),
+            RecurrenceRule::Weekdays => write!(f, "weekdays"),
+            RecurrenceRule::Weekly(days) => {
+                let days: Vec<String> = days
+                    .iter()
+                    .map(|day| day.to_string().to_lowercase())
+                    .collect();
+                write!(f, "weekly:{}", days.join(","))
+            }
+            RecurrenceRule::Monthly(day) => write!(f, "monthly:{}", day),
+        }
+    }
+}
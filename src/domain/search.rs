@@ -0,0 +1,39 @@
+use diesel::sql_types::{Double, Integer, Text};
+use diesel::QueryableByName;
+
+/// A task matching a full-text search, `rank` is the FTS5 bm25 rank so lower
+/// is a better match
+#[derive(Debug, QueryableByName, PartialEq)]
+pub struct SearchHit {
+    #[sql_type = "Integer"]
+    pub(crate) task_id: i32,
+    #[sql_type = "Double"]
+    pub(crate) rank: f64,
+    #[sql_type = "Text"]
+    pub(crate) snippet: String,
+}
+
+impl SearchHit {
+    /// Marks the start of a matched term within a snippet
+    pub const HIGHLIGHT_START: &'static str = "\u{2}";
+    /// Marks the end of a matched term within a snippet
+    pub const HIGHLIGHT_END: &'static str = "\u{3}";
+
+    /// Turns the words being searched for into an FTS5 query that matches tasks
+    /// containing all of them. Each word is quoted so punctuation such as the
+    /// dash in a ticket id is matched literally, a trailing `*` still matches
+    /// any word starting with it
+    pub fn match_query(words: &[String]) -> String {
+        words
+            .iter()
+            .flat_map(|w| w.split_whitespace())
+            .map(|word| match word.strip_suffix('*') {
+                Some(prefix) if !prefix.is_empty() => {
+                    format!("\"{}\"*", prefix.replace('"', "\"\""))
+                }
+                _ => format!("\"{}\"", word.replace('"', "\"\"")),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
@@ -0,0 +1,58 @@
+use std::cmp::{Ordering, Reverse};
+use std::convert::From;
+
+use crate::domain::Task;
+
+/// The order tasks are listed in, anything that compares equal falls back to
+/// the default ordering of status, priority and then newest opened
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+pub enum SortBy {
+    #[default]
+    Status,
+    Priority,
+    Opened,
+    Closed,
+    Due,
+    Ticket,
+}
+
+impl SortBy {
+    pub fn sort(self, tasks: &mut [Task]) {
+        tasks.sort_by(|a, b| self.compare(a, b).then_with(|| a.cmp(b)));
+    }
+
+    fn compare(self, a: &Task, b: &Task) -> Ordering {
+        match self {
+            SortBy::Status => Ordering::Equal,
+            SortBy::Priority => none_last(a.task_priority(), b.task_priority()),
+            SortBy::Opened => b.opened.cmp(&a.opened),
+            SortBy::Closed => none_last(a.closed.map(Reverse), b.closed.map(Reverse)),
+            SortBy::Due => none_last(a.due, b.due),
+            SortBy::Ticket => none_last(a.ticket.as_ref(), b.ticket.as_ref()),
+        }
+    }
+}
+
+impl From<&str> for SortBy {
+    fn from(s: &str) -> Self {
+        match s {
+            "status" => SortBy::Status,
+            "priority" => SortBy::Priority,
+            "opened" => SortBy::Opened,
+            "closed" => SortBy::Closed,
+            "due" => SortBy::Due,
+            "ticket" => SortBy::Ticket,
+            _ => panic!("unable to convert argument to sort order"),
+        }
+    }
+}
+
+/// Compares two optional values, with `None` sorting after any value
+pub(crate) fn none_last<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
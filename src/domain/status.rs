@@ -1,14 +1,85 @@
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::convert::From;
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize, Eq, Ord, PartialOrd, PartialEq, Hash, Copy, Clone)]
+use crate::domain::SuaideError;
+
+// The discriminants are what's stored in the database, so new statuses have to
+// be added to the end rather than where they'd naturally sort
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
 pub enum Status {
-    Open,
-    InProgress,
-    Closed,
-    Cancelled,
+    Open = 0,
+    InProgress = 1,
+    Blocked = 4,
+    Closed = 2,
+    Cancelled = 3,
+}
+
+impl Status {
+    /// Expands a status filter into the statuses it covers, supporting the
+    /// meta groups `active`, `done` and `all` on top of the individual statuses
+    pub fn from_filter(filter: &str) -> Vec<Status> {
+        match filter {
+            "active" => vec![Status::Open, Status::InProgress, Status::Blocked],
+            "done" => vec![Status::Closed, Status::Cancelled],
+            "all" => vec![
+                Status::Open,
+                Status::InProgress,
+                Status::Blocked,
+                Status::Closed,
+                Status::Cancelled,
+            ],
+            s => s.parse().into_iter().collect(),
+        }
+    }
+
+    /// The name of the status as it's given in arguments and exports
+    pub fn key(self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::InProgress => "in-progress",
+            Status::Blocked => "blocked",
+            Status::Closed => "closed",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    /// The human readable name of the status, without any styling
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Open => "Open",
+            Status::InProgress => "In Progress",
+            Status::Blocked => "Blocked",
+            Status::Closed => "Completed",
+            Status::Cancelled => "Cancelled",
+        }
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Status::Open => 0,
+            Status::InProgress => 1,
+            Status::Blocked => 2,
+            Status::Closed => 3,
+            Status::Cancelled => 4,
+        }
+    }
+}
+
+impl Ord for Status {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for Status {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl From<i16> for Status {
@@ -18,19 +89,27 @@ impl From<i16> for Status {
             1 => Status::InProgress,
             2 => Status::Closed,
             3 => Status::Cancelled,
+            4 => Status::Blocked,
             _ => panic!("Invalid status"),
         }
     }
 }
 
-impl From<&str> for Status {
-    fn from(s: &str) -> Self {
-        match s {
-            "open" | "o" => Status::Open,
-            "in-progress" | "inprogress" | "progress" | "ip" => Status::InProgress,
-            "closed" | "close" | "c" => Status::Closed,
-            "cancel" | "cancelled" => Status::Cancelled,
-            _ => panic!("Invalid status"),
+impl FromStr for Status {
+    type Err = SuaideError;
+
+    /// Reads a status from its key, any alias accepted as an argument or its
+    /// human readable name, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "open" | "o" => Ok(Status::Open),
+            "in-progress" | "inprogress" | "progress" | "ip" | "in progress" => {
+                Ok(Status::InProgress)
+            }
+            "blocked" | "block" | "b" => Ok(Status::Blocked),
+            "closed" | "close" | "c" | "completed" => Ok(Status::Closed),
+            "cancel" | "cancelled" => Ok(Status::Cancelled),
+            _ => Err(SuaideError::StatusFormatError(s.to_string())),
         }
     }
 }
@@ -38,10 +117,11 @@ impl From<&str> for Status {
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let text = match self {
-            Status::Open => "Open".green(),
-            Status::InProgress => "In Progress".blue(),
-            Status::Closed => "Completed".yellow(),
-            Status::Cancelled => "Cancelled".red(),
+            Status::Open => self.name().green(),
+            Status::InProgress => self.name().blue(),
+            Status::Blocked => self.name().magenta(),
+            Status::Closed => self.name().yellow(),
+            Status::Cancelled => self.name().red(),
         };
         write!(f, "{}", text.bold())
     }
@@ -0,0 +1,31 @@
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{tags, task_tags};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq)]
+pub struct Tag {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+}
+
+impl Tag {
+    /// Tags are stored trimmed and lower-cased so `Backend` and `backend`
+    /// refer to the same label
+    pub fn normalize(name: &str) -> String {
+        name.trim().to_lowercase()
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "tags"]
+pub(crate) struct AddTag {
+    pub(crate) name: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "task_tags"]
+pub(crate) struct AddTaskTag {
+    pub(crate) task_id: i32,
+    pub(crate) tag_id: i32,
+}
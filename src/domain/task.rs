@@ -1,15 +1,17 @@
-use chrono::prelude::*;
-use colored::Colorize;
 use diesel::{AsChangeset, Insertable, Queryable};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::time::{Duration, UNIX_EPOCH};
 
-use crate::common::DATE_FORMAT;
-use crate::domain::Status;
+use crate::domain::event::{format_due, format_priority, format_status, format_time};
+use crate::domain::sort::none_last;
+use crate::domain::{AddTaskEvent, Priority, Status, TaskEvent};
 use crate::schema::suaide;
 
-#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq)]
+#[derive(
+    Debug, Serialize, Deserialize, Queryable, Insertable, AsChangeset, Eq, PartialEq, Clone,
+)]
+#[table_name = "suaide"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct Task {
     pub(crate) id: i32,
     pub(crate) ticket: Option<String>,
@@ -17,6 +19,13 @@ pub struct Task {
     pub(crate) status: i16,
     pub(crate) opened: i64,
     pub(crate) closed: Option<i64>,
+    pub(crate) project_id: Option<i32>,
+    pub(crate) blocked_reason: Option<String>,
+    pub(crate) due: Option<i64>,
+    pub(crate) priority: Option<i16>,
+    pub(crate) parent_id: Option<i32>,
+    pub(crate) deleted_at: Option<i64>,
+    pub(crate) uuid: Option<String>,
 }
 
 #[derive(AsChangeset, Default)]
@@ -27,6 +36,10 @@ pub(crate) struct TaskChangeSet {
     status: Option<i16>,
     opened: Option<i64>,
     closed: Option<Option<i64>>,
+    blocked_reason: Option<Option<String>>,
+    due: Option<Option<i64>>,
+    priority: Option<Option<i16>>,
+    deleted_at: Option<Option<i64>>,
 }
 
 impl Task {
@@ -34,50 +47,29 @@ impl Task {
         self.status.into()
     }
 
-    pub fn print(&self, verbose: bool) {
-        if verbose {
-            let ticket = match &self.ticket {
-                Some(ticket) => format!("{}:", ticket),
-                None => format!("#{}:", self.id.to_string().italic()),
-            };
-            println!("[{}] {} {}", self.task_status(), ticket, self.description);
-            println!(
-                "\t{:30} {}",
-                format!("Opened: {}", self.opened_to_string()),
-                format!("Closed: {}", self.closed_to_string())
-            );
-            println!();
-            return;
-        }
-
-        let ticket = match &self.ticket {
-            Some(ticket) => format!("{}:", ticket),
-            None => format!("#{}:", self.id.to_string().italic()),
-        };
-        println!("[{}] {} {}", self.task_status(), ticket, self.description);
+    pub fn task_priority(&self) -> Option<Priority> {
+        self.priority.map(Priority::from)
     }
-}
 
-// Private API
-impl Task {
-    fn opened_to_string(&self) -> String {
-        let d = UNIX_EPOCH + Duration::from_secs(self.opened as u64);
-        let date = DateTime::<Local>::from(d);
-        date.format(DATE_FORMAT).to_string()
-    }
-
-    fn closed_to_string(&self) -> String {
-        if let Some(closed) = self.closed {
-            let d = UNIX_EPOCH + Duration::from_secs(closed as u64);
-            let date = DateTime::<Local>::from(d);
-            date.format(DATE_FORMAT).to_string()
-        } else {
-            "".to_string()
-        }
+    pub fn is_done(&self) -> bool {
+        matches!(self.task_status(), Status::Closed | Status::Cancelled)
     }
 }
 
 impl TaskChangeSet {
+    /// Diesel refuses to execute an update without any columns to set
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ticket.is_none()
+            && self.description.is_none()
+            && self.status.is_none()
+            && self.opened.is_none()
+            && self.closed.is_none()
+            && self.blocked_reason.is_none()
+            && self.due.is_none()
+            && self.priority.is_none()
+            && self.deleted_at.is_none()
+    }
+
     pub(crate) fn set_description(&mut self, task: &Task, description: String) {
         if task.description != description {
             self.description = Some(description);
@@ -90,36 +82,113 @@ impl TaskChangeSet {
         }
     }
 
-    pub(crate) fn set_status(&mut self, task: &Task, status: Status) {
+    pub(crate) fn set_status(&mut self, task: &Task, status: Status, now: i64) {
         if task.status != status as i16 {
             self.status = Some(status as i16);
             match status {
-                Status::Closed | Status::Cancelled => {
-                    self.set_closed(task, Some(Local::now().timestamp()))
-                }
+                Status::Closed | Status::Cancelled => self.set_closed(task, Some(now)),
                 _ => self.set_closed(task, None),
             };
+            if status != Status::Blocked {
+                self.set_blocked_reason(task, None);
+            }
+        }
+    }
+
+    pub(crate) fn set_blocked_reason(&mut self, task: &Task, reason: Option<String>) {
+        if task.blocked_reason != reason {
+            self.blocked_reason = Some(reason);
+        }
+    }
+
+    pub(crate) fn set_due(&mut self, task: &Task, due: Option<i64>) {
+        if task.due != due {
+            self.due = Some(due);
+        }
+    }
+
+    pub(crate) fn set_priority(&mut self, task: &Task, priority: Option<Priority>) {
+        let priority = priority.map(|p| p as i16);
+        if task.priority != priority {
+            self.priority = Some(priority);
         }
     }
 
+    /// Overrides the closed time `set_status` picks when the task is closed at
+    /// a different time to now
     pub(crate) fn set_closed(&mut self, task: &Task, closed: Option<i64>) {
-        if task.closed != closed {
-            self.closed = Some(closed);
+        self.closed = if task.closed != closed {
+            Some(closed)
+        } else {
+            None
+        };
+    }
+
+    /// Moves the task in or out of the trash
+    pub(crate) fn set_deleted(&mut self, task: &Task, deleted_at: Option<i64>) {
+        if task.deleted_at != deleted_at {
+            self.deleted_at = Some(deleted_at);
+        }
+    }
+
+    /// An event for every field this change set modifies, `opened` and
+    /// `closed` follow from the status so they aren't recorded separately
+    pub(crate) fn events(&self, task: &Task, now: i64) -> Vec<AddTaskEvent> {
+        let mut events = Vec::new();
+        let mut push = |field: &str, old_value: Option<String>, new_value: Option<String>| {
+            events.push(AddTaskEvent::new(task.id, field, old_value, new_value, now));
+        };
+        if let Some(ticket) = &self.ticket {
+            push("ticket", task.ticket.clone(), ticket.clone());
+        }
+        if let Some(description) = &self.description {
+            push(
+                "description",
+                Some(task.description.clone()),
+                Some(description.clone()),
+            );
         }
+        if let Some(status) = self.status {
+            push(
+                TaskEvent::STATUS,
+                Some(format_status(task.status)),
+                Some(format_status(status)),
+            );
+        }
+        if let Some(reason) = &self.blocked_reason {
+            push(
+                "blocked_reason",
+                task.blocked_reason.clone(),
+                reason.clone(),
+            );
+        }
+        if let Some(due) = self.due {
+            push("due", task.due.map(format_due), due.map(format_due));
+        }
+        if let Some(priority) = self.priority {
+            push(
+                "priority",
+                task.priority.map(format_priority),
+                priority.map(format_priority),
+            );
+        }
+        if let Some(deleted_at) = self.deleted_at {
+            push(
+                "deleted",
+                task.deleted_at.map(format_time),
+                deleted_at.map(format_time),
+            );
+        }
+        events
     }
 }
 
 impl Ord for Task {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.task_status().cmp(&other.task_status()) {
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Equal => match self.opened.cmp(&other.opened) {
-                Ordering::Greater => Ordering::Less,
-                Ordering::Equal => Ordering::Equal,
-                Ordering::Less => Ordering::Greater,
-            },
-            Ordering::Less => Ordering::Less,
-        }
+        self.task_status()
+            .cmp(&other.task_status())
+            .then_with(|| none_last(self.task_priority(), other.task_priority()))
+            .then_with(|| other.opened.cmp(&self.opened))
     }
 }
 
@@ -129,22 +198,41 @@ impl PartialOrd for Task {
     }
 }
 
-#[derive(Insertable)]
+#[derive(Insertable, Default)]
 #[table_name = "suaide"]
 pub(crate) struct AddTask {
     pub(crate) ticket: Option<String>,
     pub(crate) description: String,
     pub(crate) opened: i64,
     pub(crate) status: i16,
+    pub(crate) closed: Option<i64>,
+    pub(crate) project_id: Option<i32>,
+    pub(crate) blocked_reason: Option<String>,
+    pub(crate) due: Option<i64>,
+    pub(crate) priority: Option<i16>,
+    pub(crate) parent_id: Option<i32>,
+    pub(crate) uuid: Option<String>,
 }
 
 impl AddTask {
-    pub fn new(ticket: Option<String>, description: String) -> Self {
+    pub fn new(
+        ticket: Option<String>,
+        description: String,
+        project_id: Option<i32>,
+        opened: i64,
+    ) -> Self {
         Self {
             ticket,
             description,
-            opened: Local::now().timestamp(),
+            opened,
             status: 0,
+            closed: None,
+            project_id,
+            blocked_reason: None,
+            due: None,
+            priority: None,
+            parent_id: None,
+            uuid: None,
         }
     }
 }
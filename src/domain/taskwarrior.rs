@@ -0,0 +1,128 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{Priority, Status, TaskOutput};
+
+/// A task as it's written by `task export` and read by `task import`
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct TaskwarriorTask {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) uuid: Option<String>,
+    pub(crate) description: String,
+    pub(crate) status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) project: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) due: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) priority: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) annotations: Vec<TaskwarriorAnnotation>,
+    // Taskwarrior keeps any field it doesn't know about as a user defined
+    // attribute, so the fields it has no equivalent for survive a round trip
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) suaide_ticket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) suaide_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) suaide_priority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) suaide_blocked_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct TaskwarriorAnnotation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) entry: Option<String>,
+    pub(crate) description: String,
+}
+
+impl TaskwarriorTask {
+    /// Taskwarrior timestamps are always UTC, e.g. `20201001T090000Z`
+    pub const DATE_FORMAT: &'static str = "%Y%m%dT%H%M%SZ";
+
+    pub fn from_output(uuid: String, task: &TaskOutput) -> Self {
+        Self {
+            uuid: Some(uuid),
+            description: task.description.clone(),
+            status: Self::status_name(task.status).to_string(),
+            entry: Some(Self::format_date(task.opened)),
+            end: task.closed.map(Self::format_date),
+            project: task.project.clone(),
+            tags: task.tags.clone(),
+            due: task.due.map(Self::format_date),
+            priority: task
+                .priority
+                .map(|priority| Self::priority_name(priority).to_string()),
+            annotations: task
+                .notes
+                .iter()
+                .map(|note| TaskwarriorAnnotation {
+                    entry: Some(Self::format_date(note.created)),
+                    description: note.body.clone(),
+                })
+                .collect(),
+            suaide_ticket: task.ticket.clone(),
+            suaide_status: Some(task.status.key().to_string()),
+            suaide_priority: task.priority.map(|priority| priority.name().to_string()),
+            suaide_blocked_reason: task.blocked_reason.clone(),
+        }
+    }
+
+    /// Taskwarrior has no in progress or blocked tasks, they're both pending
+    /// and the actual status is kept in `suaide_status`
+    pub fn status_name(status: Status) -> &'static str {
+        match status {
+            Status::Open | Status::InProgress | Status::Blocked => "pending",
+            Status::Closed => "completed",
+            Status::Cancelled => "deleted",
+        }
+    }
+
+    /// Waiting and recurring tasks are still to be done so they're open
+    pub fn parse_status(status: &str) -> Option<Status> {
+        match status {
+            "pending" | "waiting" | "recurring" => Some(Status::Open),
+            "completed" => Some(Status::Closed),
+            "deleted" => Some(Status::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Taskwarrior only has three priorities so critical tasks are high too,
+    /// the actual priority is kept in `suaide_priority`
+    pub fn priority_name(priority: Priority) -> &'static str {
+        match priority {
+            Priority::P0 | Priority::P1 => "H",
+            Priority::P2 => "M",
+            Priority::P3 => "L",
+        }
+    }
+
+    pub fn parse_priority(priority: &str) -> Option<Priority> {
+        match priority {
+            "H" => Some(Priority::P1),
+            "M" => Some(Priority::P2),
+            "L" => Some(Priority::P3),
+            _ => None,
+        }
+    }
+
+    pub fn format_date<Tz: TimeZone>(date: DateTime<Tz>) -> String {
+        date.with_timezone(&Utc)
+            .format(Self::DATE_FORMAT)
+            .to_string()
+    }
+
+    pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+        Utc.datetime_from_str(date, Self::DATE_FORMAT).ok()
+    }
+}
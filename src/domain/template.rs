@@ -0,0 +1,79 @@
+use serde::Deserialize;
+use std::convert::From;
+
+/// The shape of a stand-up report. Each field is a single line with
+/// `{variable}` placeholders, `section` has `{section}` while `task` has
+/// `{id}`, `{ticket}`, `{ticket_link}`, `{description}`, `{status}`,
+/// `{project}`, `{tags}`, `{opened}`, `{closed}`, `{due}`, `{priority}`,
+/// `{notes}`, `{subtasks}` and `{reason}`. Blocked tasks use `blocked` instead
+/// of `task` if it's set. `link` is used to build `{ticket_link}` from
+/// `{ticket}` and `{url}` when a `ticket_url` is configured
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct StandupTemplate {
+    pub(crate) section: String,
+    pub(crate) task: String,
+    #[serde(default)]
+    pub(crate) blocked: Option<String>,
+    #[serde(default = "default_link")]
+    pub(crate) link: String,
+    #[serde(default)]
+    pub(crate) empty: String,
+}
+
+fn default_link() -> String {
+    "{ticket}".to_string()
+}
+
+/// The built-in templates selectable with `standup --format`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum StandupFormat {
+    Plain,
+    Markdown,
+    Slack,
+}
+
+impl StandupFormat {
+    pub fn template(self) -> StandupTemplate {
+        let (section, task, blocked, link, empty) = match self {
+            StandupFormat::Plain => (
+                "{section}:",
+                "- [{status}] {ticket}: {description}",
+                "- [{status}] {ticket}: {description} ({reason})",
+                "{ticket}",
+                "- Nothing",
+            ),
+            StandupFormat::Markdown => (
+                "### {section}",
+                "- **{ticket_link}** {description} _({status})_",
+                "- **{ticket_link}** {description} _({reason})_",
+                "[{ticket}]({url})",
+                "_Nothing to report_",
+            ),
+            StandupFormat::Slack => (
+                "*{section}*",
+                "• {ticket_link} {description} `{status}`",
+                "• {ticket_link} {description} _{reason}_",
+                "<{url}|{ticket}>",
+                "_Nothing to report_",
+            ),
+        };
+        StandupTemplate {
+            section: section.to_string(),
+            task: task.to_string(),
+            blocked: Some(blocked.to_string()),
+            link: link.to_string(),
+            empty: empty.to_string(),
+        }
+    }
+}
+
+impl From<&str> for StandupFormat {
+    fn from(s: &str) -> Self {
+        match s {
+            "plain" => StandupFormat::Plain,
+            "markdown" | "md" => StandupFormat::Markdown,
+            "slack" => StandupFormat::Slack,
+            _ => panic!("unable to convert argument to stand-up format"),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::time_entries;
+
+/// A period of time spent on a task, `stopped` is `None` while the timer is running
+#[derive(Debug, Serialize, Deserialize, Queryable, Eq, PartialEq)]
+pub struct TimeEntry {
+    pub(crate) id: i32,
+    pub(crate) task_id: i32,
+    pub(crate) started: i64,
+    pub(crate) stopped: Option<i64>,
+}
+
+impl TimeEntry {
+    /// The number of seconds of this entry that fall between `start` and `end`,
+    /// a running timer counts up until `now`
+    pub fn seconds_within(&self, start: i64, end: i64, now: i64) -> i64 {
+        let stopped = self.stopped.unwrap_or(now);
+        (stopped.min(end) - self.started.max(start)).max(0)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "time_entries"]
+pub(crate) struct AddTimeEntry {
+    pub(crate) task_id: i32,
+    pub(crate) started: i64,
+}
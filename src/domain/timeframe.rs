@@ -8,6 +8,16 @@ pub enum Timeframe {
     Week,
     LastWeek,
     Month,
+    Overdue,
+    Upcoming,
+}
+
+impl Timeframe {
+    /// Whether the timeframe applies to when tasks are due rather than when
+    /// they were opened or closed
+    pub fn is_due_based(self) -> bool {
+        matches!(self, Timeframe::Overdue | Timeframe::Upcoming)
+    }
 }
 
 impl From<&str> for Timeframe {
@@ -18,6 +28,8 @@ impl From<&str> for Timeframe {
             "week" => Timeframe::Week,
             "lastweek" => Timeframe::LastWeek,
             "month" => Timeframe::Month,
+            "overdue" => Timeframe::Overdue,
+            "upcoming" => Timeframe::Upcoming,
             _ => panic!("unable to convert argument to timeframe"),
         }
     }
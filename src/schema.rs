@@ -1,3 +1,42 @@
+table! {
+    journal (id) {
+        id -> Integer,
+        command -> Text,
+        before_tasks -> Text,
+        after_tasks -> Text,
+        created -> BigInt,
+        undone -> Bool,
+    }
+}
+
+table! {
+    notes (id) {
+        id -> Integer,
+        task_id -> Integer,
+        body -> Text,
+        created -> BigInt,
+    }
+}
+
+table! {
+    projects (id) {
+        id -> Integer,
+        name -> Text,
+        prefix -> Text,
+        active -> Bool,
+    }
+}
+
+table! {
+    recurrences (id) {
+        id -> Integer,
+        task_id -> Integer,
+        rule -> Text,
+        next_due -> BigInt,
+        last_task_id -> Integer,
+    }
+}
+
 table! {
     suaide (id) {
         id -> Integer,
@@ -6,5 +45,74 @@ table! {
         status -> SmallInt,
         opened -> BigInt,
         closed -> Nullable<BigInt>,
+        project_id -> Nullable<Integer>,
+        blocked_reason -> Nullable<Text>,
+        due -> Nullable<BigInt>,
+        priority -> Nullable<SmallInt>,
+        parent_id -> Nullable<Integer>,
+        deleted_at -> Nullable<BigInt>,
+        uuid -> Nullable<Text>,
+    }
+}
+
+table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+table! {
+    task_dependencies (task_id, depends_on) {
+        task_id -> Integer,
+        depends_on -> Integer,
+    }
+}
+
+table! {
+    task_events (id) {
+        id -> Integer,
+        task_id -> Integer,
+        field -> Text,
+        old_value -> Nullable<Text>,
+        new_value -> Nullable<Text>,
+        created -> BigInt,
+    }
+}
+
+table! {
+    task_tags (task_id, tag_id) {
+        task_id -> Integer,
+        tag_id -> Integer,
+    }
+}
+
+table! {
+    time_entries (id) {
+        id -> Integer,
+        task_id -> Integer,
+        started -> BigInt,
+        stopped -> Nullable<BigInt>,
     }
 }
+
+joinable!(notes -> suaide (task_id));
+joinable!(suaide -> projects (project_id));
+joinable!(task_dependencies -> suaide (depends_on));
+joinable!(task_events -> suaide (task_id));
+joinable!(task_tags -> suaide (task_id));
+joinable!(task_tags -> tags (tag_id));
+joinable!(time_entries -> suaide (task_id));
+
+allow_tables_to_appear_in_same_query!(
+    journal,
+    notes,
+    projects,
+    recurrences,
+    suaide,
+    tags,
+    task_dependencies,
+    task_events,
+    task_tags,
+    time_entries,
+);
@@ -1,10 +1,12 @@
-use config::{Config, ConfigError, Environment, File};
+use chrono::{NaiveDate, Weekday};
+use config::{Config, ConfigError, Environment, File, FileFormat};
 use lazy_static::lazy_static;
 use serde::Deserialize;
 use std::fs;
 use std::path::Path;
 
-use crate::domain::SuaideError;
+use crate::common::time::parse_date;
+use crate::domain::{StandupTemplate, SuaideError};
 
 lazy_static! {
     static ref DEFAULT_SUAIDE_PATH: &'static str = "~/.suaide";
@@ -15,6 +17,10 @@ lazy_static! {
 pub struct Settings {
     pub(crate) db_url: String,
     pub(crate) ticket_prefix: String,
+    pub(crate) working_days: Vec<String>,
+    pub(crate) holidays_file: Option<String>,
+    pub(crate) standup_template: Option<String>,
+    pub(crate) ticket_url: Option<String>,
 }
 
 impl Settings {
@@ -23,6 +29,7 @@ impl Settings {
 
         s.set_default("db_url", DEFAULT_DB_PATH.as_ref())?;
         s.set_default("ticket_prefix", "")?;
+        s.set_default("working_days", vec!["mon", "tue", "wed", "thu", "fri"])?;
 
         let config_name =
             shellexpand::tilde(&format!("{}/settings.yml", DEFAULT_SUAIDE_PATH.to_string()))
@@ -48,6 +55,47 @@ impl Settings {
     pub fn generate_ticket_id(&self, ticket: String) -> String {
         format!("{}{}", self.ticket_prefix, ticket)
     }
+
+    pub fn get_working_days(&self) -> Result<Vec<Weekday>, SuaideError> {
+        self.working_days
+            .iter()
+            .map(|day| {
+                day.parse::<Weekday>()
+                    .map_err(|_| SuaideError::WorkingDayError(day.to_string()))
+            })
+            .collect()
+    }
+
+    /// Reads the holidays file if one is configured, it should contain a
+    /// date per line with blank lines and lines starting with `#` ignored
+    pub fn get_holidays(&self) -> Result<Vec<NaiveDate>, SuaideError> {
+        let path = match &self.holidays_file {
+            Some(path) => shellexpand::tilde(path).to_string(),
+            None => return Ok(Vec::new()),
+        };
+        fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(parse_date)
+            .collect()
+    }
+
+    /// Loads the stand-up template file if one is configured, it's a YAML file
+    /// with the same fields as `StandupTemplate`
+    pub fn get_standup_template(&self) -> Result<Option<StandupTemplate>, SuaideError> {
+        let path = match &self.standup_template {
+            Some(path) => shellexpand::tilde(path).to_string(),
+            None => return Ok(None),
+        };
+        let mut template = Config::new();
+        template.merge(File::new(&path, FileFormat::Yaml))?;
+        Ok(Some(template.try_into()?))
+    }
+
+    pub fn get_ticket_url(&self) -> Option<&str> {
+        self.ticket_url.as_deref()
+    }
 }
 
 fn verify_or_setup_folder_structure(path: String) -> Result<(), SuaideError> {
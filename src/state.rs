@@ -1,11 +1,16 @@
+use chrono::{DateTime, Local};
 use colored::Colorize;
-use dialoguer::Input;
 use diesel::SqliteConnection;
 use std::io;
 
+use crate::common::clock::{Clock, SystemClock};
+#[cfg(not(test))]
+use crate::common::inputs::DialoguerPrompter;
+use crate::common::inputs::Prompter;
+use crate::common::storage::{get_active_project, get_task};
 use crate::common::{ADD_PREFIX, EDIT_PREFIX};
 use crate::database::establish_connection;
-use crate::domain::SuaideError;
+use crate::domain::{OutputFormat, Project, SuaideError, Task};
 use crate::settings::Settings;
 
 pub struct State<W>
@@ -13,7 +18,13 @@ where
     W: io::Write,
 {
     settings: Settings,
+    // The configured ticket prefix, for when a project doesn't have its own
+    global_prefix: String,
     conn: SqliteConnection,
+    project: Option<Project>,
+    output: OutputFormat,
+    prompter: Box<dyn Prompter>,
+    clock: Box<dyn Clock>,
     w: W,
 }
 
@@ -32,11 +43,19 @@ where
         }
 
         let conn = establish_connection(&settings.db_url)?;
-        Ok(State {
+        let project = get_active_project(&conn)?;
+        let mut state = State {
+            global_prefix: settings.ticket_prefix.clone(),
             settings,
             conn,
+            project: None,
+            output: OutputFormat::default(),
+            prompter: default_prompter(),
+            clock: Box::new(SystemClock),
             w: writer,
-        })
+        };
+        state.set_project(project);
+        Ok(state)
     }
 
     pub fn get_conn(&self) -> &SqliteConnection {
@@ -54,40 +73,85 @@ where
         None
     }
 
+    /// Finds a task by its ticket, with the ticket prefix applied, or its id
+    pub fn find_task(&self, task: &str) -> Result<Task, SuaideError> {
+        let ticket_id = self.generate_ticket_id(Some(task)).unwrap();
+        get_task(&ticket_id, self.get_conn()).or_else(|_| get_task(task, self.get_conn()))
+    }
+
+    pub fn get_settings(&self) -> &Settings {
+        &self.settings
+    }
+
     pub fn get_ticket_prefix(&self) -> &str {
         &self.settings.ticket_prefix
     }
 
     pub fn set_prefix(&mut self, prefix: String) {
+        self.global_prefix = prefix.clone();
         self.settings.ticket_prefix = prefix;
     }
 
+    pub fn get_output_format(&self) -> OutputFormat {
+        self.output
+    }
+
+    pub fn set_output_format(&mut self, output: OutputFormat) {
+        self.output = output;
+    }
+
+    pub fn get_project(&self) -> Option<&Project> {
+        self.project.as_ref()
+    }
+
+    /// Switches the project used for new tasks and scoped queries, a project
+    /// with a prefix takes over from the globally configured ticket prefix and
+    /// any other project goes back to it
+    pub fn set_project(&mut self, project: Option<Project>) {
+        self.settings.ticket_prefix = match project.as_ref().map(|p| &p.prefix) {
+            Some(prefix) if !prefix.is_empty() => prefix.clone(),
+            _ => self.global_prefix.clone(),
+        };
+        self.project = project;
+    }
+
     pub fn writer(&mut self) -> &mut W {
         &mut self.w
     }
 
+    pub fn now(&self) -> DateTime<Local> {
+        self.clock.now()
+    }
+
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn prompter(&mut self) -> &mut dyn Prompter {
+        self.prompter.as_mut()
+    }
+
+    #[cfg(test)]
+    pub fn set_prompter(&mut self, prompter: Box<dyn Prompter>) {
+        self.prompter = prompter;
+    }
+
     pub fn get_input(
         &mut self,
         key: &str,
         existing_data: Option<String>,
     ) -> Result<String, SuaideError> {
-        // @todo - find a way to properly test Dialoguer
-        if cfg!(test) {
-            return Ok("MOCK DATA".to_string());
-        }
-
         let prefix = if existing_data.is_some() {
             EDIT_PREFIX
         } else {
             ADD_PREFIX
         };
 
-        let mut input = Input::<String>::new();
-        let input = input
-            .with_prompt(format!("{} task {}", prefix, key))
-            .allow_empty(false)
-            .with_initial_text(existing_data.unwrap_or_default());
-        Ok(input.interact()?)
+        self.prompter.input(
+            &format!("{} task {}", prefix, key),
+            &existing_data.unwrap_or_default(),
+            false,
+        )
     }
 
     pub fn get_optional_input(
@@ -95,23 +159,17 @@ where
         key: &str,
         existing_data: Option<String>,
     ) -> Result<Option<String>, SuaideError> {
-        // @todo - find a way to properly test Dialoguer
-        if cfg!(test) {
-            return Ok(Some("MOCK DATA".to_string()));
-        }
-
         let (prefix, suffix) = if existing_data.is_some() {
             (EDIT_PREFIX, "".italic())
         } else {
             (ADD_PREFIX, "(Enter to skip)".italic())
         };
 
-        let mut input = Input::<String>::new();
-        let input = input
-            .with_prompt(format!("{} task {} {}", prefix, key, suffix))
-            .allow_empty(true)
-            .with_initial_text(existing_data.unwrap_or_default());
-        let final_input = input.interact()?;
+        let final_input = self.prompter.input(
+            &format!("{} task {} {}", prefix, key, suffix),
+            &existing_data.unwrap_or_default(),
+            true,
+        )?;
 
         if final_input.is_empty() {
             return Ok(None);
@@ -121,28 +179,76 @@ where
     }
 }
 
+#[cfg(not(test))]
+fn default_prompter() -> Box<dyn Prompter> {
+    Box::new(DialoguerPrompter)
+}
+
+// Tests have to script any answers they expect to be prompted for
+#[cfg(test)]
+fn default_prompter() -> Box<dyn Prompter> {
+    Box::new(crate::common::inputs::ScriptedPrompter::default())
+}
+
 #[cfg(test)]
 mod test_state_methods {
     use super::*;
 
-    // These tests are kind of useless?
-    // Look into how to improve them
+    use crate::common::inputs::ScriptedPrompter;
 
     #[test]
     fn get_input_without_data() {
         let mut writer = Vec::new();
         let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().input("Answer")));
         let output = state.get_input("TEST", None);
         assert!(output.is_ok());
-        assert_eq!(output.unwrap(), "MOCK DATA".to_string());
+        assert_eq!(output.unwrap(), "Answer".to_string());
     }
 
     #[test]
     fn get_input_with_data() {
         let mut writer = Vec::new();
         let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().input("Edited Text")));
         let output = state.get_input("TEST", Some("EXISTING TEXT".to_string()));
         assert!(output.is_ok());
-        assert_eq!(output.unwrap(), "MOCK DATA".to_string());
+        assert_eq!(output.unwrap(), "Edited Text".to_string());
+    }
+
+    fn project(prefix: &str) -> Project {
+        Project {
+            id: 1,
+            name: "Project".to_string(),
+            prefix: prefix.to_string(),
+            active: true,
+        }
+    }
+
+    #[test]
+    fn project_without_a_prefix_uses_the_global_prefix() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prefix("GLOBAL-".to_string());
+
+        state.set_project(Some(project("PRJ-")));
+        assert_eq!(state.get_ticket_prefix(), "PRJ-");
+
+        state.set_project(Some(project("")));
+        assert_eq!(state.get_ticket_prefix(), "GLOBAL-");
+
+        state.set_project(Some(project("PRJ-")));
+        state.set_project(None);
+        assert_eq!(state.get_ticket_prefix(), "GLOBAL-");
+    }
+
+    #[test]
+    fn get_optional_input_skipped() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().input("")));
+        let output = state.get_optional_input("TEST", None);
+        assert!(output.is_ok());
+        assert_eq!(output.unwrap(), None);
     }
 }
@@ -1,10 +1,18 @@
 use clap::{App, Arg, ArgMatches};
 use colored::Colorize;
 use std::io;
+use std::slice;
 
 use diesel::prelude::*;
 
-use crate::domain::{AddTask, SuaideError};
+use crate::common::args::{tags_arg, values_to_vec};
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    add_recurrence, add_tags_to_task, get_last_inserted_task, get_task_output, get_trashed_task,
+    record_events, record_journal,
+};
+use crate::common::time::{end_of_day, parse_due_date, timestamp_to_local};
+use crate::domain::{AddTask, AddTaskEvent, Priority, RecurrenceRule, SuaideError};
 use crate::schema::suaide;
 use crate::state::State;
 
@@ -26,6 +34,38 @@ pub fn app<'a>() -> App<'a, 'static> {
                 .help("Description")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("due")
+                .long("due")
+                .help("When the task is due, e.g. 2020-10-09, friday or +3d")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("priority")
+                .long("priority")
+                .help("How urgent the task is, from p0 (most urgent) to p3")
+                .possible_values(&["p0", "p1", "p2", "p3", "critical", "high", "medium", "low"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("parent")
+                .long("parent")
+                .help("The task this is a subtask of")
+                .takes_value(true),
+        )
+        .arg(tags_arg(
+            "tag",
+            "Label to attach to the task, can be repeated or comma separated",
+        ))
+        .arg(
+            Arg::with_name("recur")
+                .long("recur")
+                .help("Repeat the task, e.g. daily, weekdays, weekly:mon,thu or monthly:15")
+                .long_help(
+                    "Repeat the task, e.g. daily, weekdays, weekly:mon,thu or monthly:15.\nThe task is due on the first matching day from its due date, or from today, and a new one is added once it's done or the next day comes around\n",
+                )
+                .takes_value(true),
+        )
 }
 
 pub fn handler<W: io::Write>(
@@ -47,25 +87,78 @@ pub fn handler<W: io::Write>(
         ticket = state.generate_ticket_id(result.1);
     }
 
-    let task = AddTask::new(ticket, description);
-    match diesel::insert_into(suaide::table)
-        .values(&task)
-        .execute(state.get_conn())
-    {
-        Err(diesel::result::Error::DatabaseError(
-            diesel::result::DatabaseErrorKind::UniqueViolation,
-            _,
-        )) => Err(SuaideError::TicketAlreadyExistsError),
-        Err(e) => Err(SuaideError::from(e)),
-        Ok(x) => Ok(x),
-    }?;
-    writeln!(
+    let project = state.get_project().map(|p| p.id);
+    let mut task = AddTask::new(ticket, description, project, state.now().timestamp());
+    if let Some(due) = matches.value_of("due") {
+        task.due = Some(parse_due_date(due, state.now().date())?);
+    }
+    if let Some(parent) = matches.value_of("parent") {
+        task.parent_id = Some(state.find_task(parent)?.id);
+    }
+    task.priority = matches
+        .value_of("priority")
+        .map(str::parse::<Priority>)
+        .transpose()?
+        .map(|p| p as i16);
+    let recurrence = matches
+        .value_of("recur")
+        .map(str::parse::<RecurrenceRule>)
+        .transpose()?;
+    if let Some(rule) = &recurrence {
+        let from = task
+            .due
+            .map_or(state.now().date(), |due| timestamp_to_local(due).date());
+        task.due = Some(end_of_day(rule.first_from(from).naive_local())?);
+    }
+    // The task is stored along with its history, tags and recurrence or not at all
+    let conn = state.get_conn();
+    let inserted = conn.transaction::<_, SuaideError, _>(|| {
+        match diesel::insert_into(suaide::table)
+            .values(&task)
+            .execute(conn)
+        {
+            Err(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            )) => match task.ticket.as_deref() {
+                Some(ticket) if get_trashed_task(ticket, conn).is_ok() => {
+                    Err(SuaideError::TicketInTrashError(ticket.to_string()))
+                }
+                _ => Err(SuaideError::TicketAlreadyExistsError),
+            },
+            Err(e) => Err(SuaideError::from(e)),
+            Ok(x) => Ok(x),
+        }?;
+
+        let inserted = get_last_inserted_task(conn)?;
+        record_journal("add", &[], slice::from_ref(&inserted), task.opened, conn)?;
+        record_events(&[AddTaskEvent::added(&inserted, task.opened)], conn)?;
+        add_tags_to_task(inserted.id, &values_to_vec(matches, "tag"), conn)?;
+        if let (Some(rule), Some(due)) = (&recurrence, inserted.due) {
+            let next_due = end_of_day(
+                rule.next_after(timestamp_to_local(due).date())
+                    .naive_local(),
+            )?;
+            add_recurrence(inserted.id, rule, next_due, conn)?;
+        }
+        Ok(inserted)
+    })?;
+
+    let output = get_task_output(&inserted, state.get_conn())?;
+    let message = match recurrence {
+        Some(rule) => format!(
+            "{}: {} (repeats {})",
+            "Added task".green(),
+            task.description,
+            rule
+        ),
+        None => format!("{}: {}", "Added task".green(), task.description),
+    };
+    TaskRenderer::new(state.get_output_format(), false).render_message(
         state.writer(),
-        "{}: {}",
-        "Added task".green(),
-        task.description
-    )?;
-    Ok(())
+        &output,
+        &message,
+    )
 }
 
 fn grab_input_from_user<W: io::Write>(
@@ -80,7 +173,11 @@ fn grab_input_from_user<W: io::Write>(
 mod test_add_app {
     use super::*;
 
-    use crate::domain::{Status, Task};
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::storage::{add_project, get_tags_for_task};
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::domain::{AddProject, Status, Task};
     use crate::schema::suaide::dsl::*;
     use crate::state::State;
 
@@ -190,6 +287,153 @@ mod test_add_app {
         assert!(data.contains(EXPECTED_STDOUT_OUTPUT));
     }
 
+    #[test]
+    fn test_tags_are_attached() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let matches = app().get_matches_from(vec![
+            "add",
+            "-d",
+            "Test Description",
+            "--tag",
+            "Backend",
+            "--tag",
+            "oncall,review",
+        ]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let tags = get_tags_for_task(1, state.get_conn()).expect("This should return an Ok");
+        assert_eq!(
+            tags,
+            vec![
+                "backend".to_string(),
+                "oncall".to_string(),
+                "review".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_task_is_added_to_active_project() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let project = AddProject {
+            name: "backend".to_string(),
+            prefix: "BE-".to_string(),
+        };
+        let project = add_project(&project, state.get_conn()).unwrap();
+        state.set_project(Some(project));
+
+        let matches = app().get_matches_from(vec!["add", "-t", "1234", "-d", "Test Description"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let db_conn = state.get_conn();
+        let result: Task = suaide
+            .find(1)
+            .first(db_conn)
+            .expect("This should return an Ok");
+
+        assert_eq!(result.ticket, Some("BE-1234".to_string()));
+        assert_eq!(result.project_id, Some(1));
+    }
+
+    #[test]
+    fn test_json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+        let matches = app().get_matches_from(vec![
+            "add",
+            "-t",
+            "1234",
+            "-d",
+            "Test Description",
+            "--tag",
+            "backend",
+        ]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["id"], 1);
+        assert_eq!(output["ticket"], "1234");
+        assert_eq!(output["description"], "Test Description");
+        assert_eq!(output["status"], "open");
+        assert_eq!(output["closed"], serde_json::Value::Null);
+        assert_eq!(output["tags"], serde_json::json!(["backend"]));
+        assert!(output["opened"].as_str().unwrap().contains('T'));
+    }
+
+    #[test]
+    fn test_due_date() {
+        use crate::common::clock::FixedClock;
+        use chrono::prelude::*;
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_clock(Box::new(FixedClock::new(
+            Local.ymd(2020, 10, 7).and_hms(9, 0, 0),
+        )));
+        let matches = app().get_matches_from(vec!["add", "-d", "Test Description", "--due", "fri"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(
+            result.due,
+            Some(Local.ymd(2020, 10, 9).and_hms(23, 59, 59).timestamp())
+        );
+    }
+
+    #[test]
+    fn test_parent() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        let matches = app().get_matches_from(vec!["add", "-d", "Subtask", "--parent", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(2)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.parent_id, Some(1));
+    }
+
+    #[test]
+    fn test_missing_parent() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["add", "-d", "Subtask", "--parent", "99"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let matches =
+            app().get_matches_from(vec!["add", "-d", "Test Description", "--priority", "high"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.priority, Some(Priority::P1 as i16));
+    }
+
     #[test]
     fn test_full_flag_inputs_short_errors_with_no_description() {
         let matches = app().get_matches_from_safe(vec!["add", "-t", "1234"]);
@@ -207,7 +451,7 @@ mod test_add_app {
         let mut writer = Vec::new();
         let mut state = State::new(&mut writer).unwrap();
 
-        test_helpers::insert_task("1234".to_string(), state.get_conn());
+        testing::task().ticket("1234").insert(state.get_conn());
 
         let matches = app().get_matches_from(vec!["add", "-t", "1234", "-d", "Test Description"]);
         let result = handler(&matches, &mut state).unwrap_err();
@@ -221,6 +465,11 @@ mod test_add_app {
     fn test_prompts() {
         let mut writer = Vec::new();
         let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(
+            ScriptedPrompter::default()
+                .input("Prompted Description")
+                .input("5678"),
+        ));
         let matches = app().get_matches_from(vec!["add"]);
         let result = handler(&matches, &mut state);
         assert!(result.is_ok());
@@ -232,8 +481,8 @@ mod test_add_app {
             .expect("This should return an Ok");
 
         assert_eq!(result.id, 1);
-        assert_eq!(result.ticket, Some("MOCK DATA".to_string()));
-        assert_eq!(result.description, "MOCK DATA".to_string());
+        assert_eq!(result.ticket, Some("5678".to_string()));
+        assert_eq!(result.description, "Prompted Description".to_string());
         assert_eq!(result.status, Status::Open as i16);
         assert_eq!(result.closed, None);
 
@@ -241,12 +490,38 @@ mod test_add_app {
         assert!(data.contains(EXPECTED_STDOUT_OUTPUT));
     }
 
+    #[test]
+    fn test_prompts_without_ticket() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(
+            ScriptedPrompter::default()
+                .input("Prompted Description")
+                .input(""),
+        ));
+        let matches = app().get_matches_from(vec!["add"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.ticket, None);
+        assert_eq!(result.description, "Prompted Description".to_string());
+    }
+
     #[test]
     fn test_prompts_error_on_duplicate_id() {
         let mut writer = Vec::new();
         let mut state = State::new(&mut writer).unwrap();
 
-        test_helpers::insert_task("MOCK DATA".to_string(), state.get_conn());
+        testing::task().ticket("5678").insert(state.get_conn());
+        state.set_prompter(Box::new(
+            ScriptedPrompter::default()
+                .input("Prompted Description")
+                .input("5678"),
+        ));
 
         let matches = app().get_matches_from(vec!["add"]);
         let result = handler(&matches, &mut state).unwrap_err();
@@ -256,23 +531,3 @@ mod test_add_app {
         }
     }
 }
-
-#[cfg(test)]
-mod test_helpers {
-    use super::AddTask;
-    use diesel::prelude::*;
-
-    pub fn insert_task(ticket: String, db_conn: &SqliteConnection) {
-        let task = AddTask {
-            ticket: Some(ticket),
-            description: "Test Description".to_string(),
-            status: 0,
-            opened: 10000,
-        };
-
-        diesel::insert_into(crate::schema::suaide::table)
-            .values(task)
-            .execute(db_conn)
-            .expect("Insert should be successful");
-    }
-}
@@ -1,70 +1,86 @@
-use chrono::prelude::*;
 use clap::{App, Arg, ArgMatches};
 use colored::Colorize;
 use std::io;
 
-use diesel::prelude::*;
-
-use crate::domain::{Status, SuaideError};
+use crate::common::args::values_to_vec;
+use crate::common::inputs::confirm_closing_parent;
+use crate::common::render::TaskRenderer;
+use crate::common::selection::{confirm_selection, resolve_tasks, selection_args, Selection};
+use crate::common::storage::{get_task_outputs, update_tasks};
+use crate::domain::{Status, SuaideError, TaskChangeSet, TaskOutput};
 use crate::state::State;
 
 pub fn app<'a>() -> App<'a, 'static> {
-    App::new("close").about("Mark a task as closed").arg(
-        Arg::with_name("task")
-            .index(1)
-            .help("The task to mark as closed")
-            .required(true)
-            .takes_value(true),
-    )
+    App::new("close")
+        .about("Mark tasks as closed")
+        .args(&selection_args("The tasks to mark as closed"))
+        .arg(
+            Arg::with_name("force")
+                .long("force")
+                .short("f")
+                .help("Close the tasks even if they have open subtasks"),
+        )
 }
 
 pub fn handler<W: io::Write>(
     matches: &ArgMatches,
     state: &mut State<W>,
 ) -> Result<(), SuaideError> {
-    if let Some(task) = matches.value_of("task") {
-        return update_task(task, state);
+    let values = values_to_vec(matches, "task");
+    let selection = resolve_tasks(&values, matches, state)?;
+    if !confirm_selection(state, &selection, "closed", matches)? {
+        return Ok(());
     }
-    Err(SuaideError::IncorrectArgs)
+    close_tasks(&selection, matches.is_present("force"), state)
 }
 
-fn update_task<W: io::Write>(task: &str, state: &mut State<W>) -> Result<(), SuaideError> {
-    use crate::schema::suaide::dsl::{closed, status, suaide, ticket};
-
-    let update = (
-        closed.eq(Some(Local::now().timestamp())),
-        status.eq(Status::Closed as i16),
-    );
-
-    if let Ok(result) =
-        diesel::update(suaide.filter(ticket.eq(state.generate_ticket_id(Some(task)))))
-            .set(update)
-            .execute(state.get_conn())
-    {
-        if result == 1 {
-            writeln!(state.writer(), "[{}]: {}", "Completed".yellow(), task)?;
-            return Ok(());
-        }
+fn close_tasks<W: io::Write>(
+    selection: &Selection,
+    force: bool,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let ids: Vec<i32> = selection.tasks.iter().map(|t| t.id).collect();
+    for task in &selection.tasks {
+        confirm_closing_parent(state, task, &ids, force)?;
     }
 
-    if let Ok(num) = task.parse::<i32>() {
-        if let Ok(result) = diesel::update(suaide.find(num))
-            .set(update)
-            .execute(state.get_conn())
-        {
-            if result == 1 {
-                writeln!(state.writer(), "[{}]: {}", "Completed".yellow(), task)?;
-                return Ok(());
-            }
-        }
+    let now = state.now().timestamp();
+    let closed = update_tasks("close", &selection.tasks, now, state.get_conn(), |task| {
+        let mut change_set = TaskChangeSet::default();
+        change_set.set_status(task, Status::Closed, now);
+        change_set
+    })?;
+
+    let output = get_task_outputs(&closed, state.get_conn())?;
+    let message: Vec<String> = closed
+        .iter()
+        .map(|task| {
+            format!(
+                "[{}]: {}",
+                "Completed".yellow(),
+                TaskOutput::reference(task)
+            )
+        })
+        .collect();
+    let renderer = TaskRenderer::new(state.get_output_format(), false);
+    if selection.bulk {
+        renderer.render_message(state.writer(), &output, &message.join("\n"))
+    } else {
+        renderer.render_message(state.writer(), &output[0], &message.join("\n"))
     }
-    Err(SuaideError::NotFound)
 }
 
 #[cfg(test)]
 mod test_close_app {
     use super::*;
 
+    use chrono::prelude::*;
+    use diesel::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
     use crate::domain::{Status, Task};
     use crate::schema::suaide::dsl::*;
     use crate::state::State;
@@ -78,7 +94,7 @@ mod test_close_app {
         let mut writer = Vec::new();
         let mut state = State::new(&mut writer).unwrap();
 
-        test_helpers::insert_task(state.get_conn());
+        testing::task().ticket("1234").insert(state.get_conn());
 
         let matches = app().get_matches_from(vec!["close", "1234"]);
         let result = handler(&matches, &mut state);
@@ -100,6 +116,203 @@ mod test_close_app {
         assert_eq!(data, EXPECTED_STDOUT_OUTPUT);
     }
 
+    #[test]
+    fn uses_the_current_time_from_the_clock() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["close", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.closed, Some(now.timestamp()));
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["close", "1"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["id"], 1);
+        assert_eq!(output["ticket"], "1234");
+        assert_eq!(output["status"], "closed");
+        assert!(output["closed"].is_string());
+    }
+
+    #[test]
+    fn refuses_to_close_a_parent_with_open_subtasks() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(false)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Subtask")
+            .parent(1)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["close", "1234"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::OpenSubtasksError(1))
+        ));
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.status, Status::Open as i16);
+    }
+
+    #[test]
+    fn closes_a_parent_once_confirmed() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(true)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Subtask")
+            .parent(1)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["close", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+    }
+
+    #[test]
+    fn force_closes_a_parent_without_prompting() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Subtask")
+            .parent(1)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["close", "1234", "--force"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.status, Status::Closed as i16);
+    }
+
+    #[test]
+    fn closes_a_range_along_with_its_subtasks() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Subtask")
+            .parent(1)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Subtask")
+            .parent(1)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["close", "1..3", "--yes"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let closed_count: i64 = suaide
+            .filter(status.eq(Status::Closed as i16))
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(closed_count, 3);
+
+        // Whether the output is coloured depends on the terminal, so only the
+        // plain text is checked
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.contains("Completed")));
+        assert!(lines[0].ends_with("]: 1234"));
+        assert!(lines[1].ends_with("]: #2"));
+        assert!(lines[2].ends_with("]: #3"));
+    }
+
+    #[test]
+    fn previews_the_tasks_before_closing_them() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(false)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Subtask")
+            .parent(1)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["close", "1234", "2"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let closed_count: i64 = suaide
+            .filter(status.eq(Status::Closed as i16))
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(closed_count, 0);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.starts_with("2 task(s) will be closed:\n"));
+        assert!(data.contains("Test Description"));
+        assert!(data.contains("Subtask"));
+    }
+
+    #[test]
+    fn closes_tasks_matching_the_filters() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Subtask")
+            .parent(1)
+            .insert(state.get_conn());
+        diesel::update(suaide.find(2))
+            .set(status.eq(Status::InProgress as i16))
+            .execute(state.get_conn())
+            .unwrap();
+
+        let matches = app().get_matches_from(vec!["close", "--status", "in-progress"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::ConfirmationRequired(1))
+        ));
+
+        let matches = app().get_matches_from(vec!["close", "--status", "in-progress", "-y"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output.as_array().map(Vec::len), Some(1));
+        assert_eq!(output[0]["id"], 2);
+        assert_eq!(output[0]["status"], "closed");
+    }
+
     #[test]
     fn should_error_with_not_found() {
         let mut writer = Vec::new();
@@ -112,23 +325,3 @@ mod test_close_app {
         };
     }
 }
-
-#[cfg(test)]
-mod test_helpers {
-    use crate::domain::AddTask;
-    use diesel::prelude::*;
-
-    pub fn insert_task(db_conn: &SqliteConnection) {
-        let task = AddTask {
-            ticket: Some("1234".to_string()),
-            description: "Test Description".to_string(),
-            status: 0,
-            opened: 10000,
-        };
-
-        diesel::insert_into(crate::schema::suaide::table)
-            .values(task)
-            .execute(db_conn)
-            .expect("Insert should be successful");
-    }
-}
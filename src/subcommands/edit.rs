@@ -3,8 +3,15 @@ use clap::{App, Arg, ArgMatches};
 use diesel::prelude::*;
 use std::io;
 
-use crate::common::{inputs::get_state_input, storage::get_task};
-use crate::domain::{SuaideError, Task, TaskChangeSet};
+use crate::common::args::{tags_arg, values_to_vec};
+use crate::common::inputs::{confirm_closing_parent, get_blocked_reason, get_state_input};
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    add_tags_to_task, get_tags_for_task, get_task, get_task_output, record_events,
+    record_snapshots, remove_tags_from_task, update_task,
+};
+use crate::common::time::parse_due_date;
+use crate::domain::{AddTaskEvent, Status, SuaideError, Task, TaskChangeSet, TaskSnapshot};
 use crate::state::State;
 
 pub fn app<'a>() -> App<'a, 'static> {
@@ -23,6 +30,29 @@ pub fn app<'a>() -> App<'a, 'static> {
                 .short("v")
                 .help("Provide additional information about each task"),
         )
+        .arg(
+            Arg::with_name("due")
+                .long("due")
+                .help("When the task is due, e.g. 2020-10-09, friday or +3d, or none to clear it")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("priority")
+                .long("priority")
+                .help("How urgent the task is, from p0 (most urgent) to p3, or none to clear it")
+                .possible_values(&[
+                    "p0", "p1", "p2", "p3", "critical", "high", "medium", "low", "none",
+                ])
+                .takes_value(true),
+        )
+        .arg(tags_arg(
+            "tag",
+            "Label to attach to the task, can be repeated or comma separated",
+        ))
+        .arg(tags_arg(
+            "untag",
+            "Label to remove from the task, can be repeated or comma separated",
+        ))
 }
 
 pub fn handler<W: io::Write>(
@@ -33,22 +63,72 @@ pub fn handler<W: io::Write>(
     if let Some(task_id) = matches.value_of("task") {
         let task_id = state.generate_ticket_id(Some(task_id)).unwrap();
         let task = get_task(&task_id, state.get_conn())?;
-        let change_set = grab_input_from_user(&task, state)?;
+        // Only the fields given as flags are changed when there are any,
+        // otherwise the user is prompted for the rest
+        let flags = ["due", "priority", "tag", "untag"];
+        let mut change_set = if flags.iter().any(|flag| matches.is_present(flag)) {
+            TaskChangeSet::default()
+        } else {
+            grab_input_from_user(&task, state)?
+        };
+        match matches.value_of("due") {
+            Some("none") => change_set.set_due(&task, None),
+            Some(due) => change_set.set_due(&task, Some(parse_due_date(due, state.now().date())?)),
+            None => {}
+        }
+        match matches.value_of("priority") {
+            Some("none") => change_set.set_priority(&task, None),
+            Some(priority) => change_set.set_priority(&task, Some(priority.parse()?)),
+            None => {}
+        }
+
+        let now = state.now().timestamp();
+        // The edit is stored along with its tags and history or not at all
+        let conn = state.get_conn();
+        let updated = conn.transaction::<_, SuaideError, _>(|| {
+            update_task(&task, change_set, now, conn)?;
 
-        use crate::schema::suaide::dsl::{id, suaide};
+            let existing_tags = get_tags_for_task(task.id, conn)?;
+            add_tags_to_task(task.id, &values_to_vec(matches, "tag"), conn)?;
+            remove_tags_from_task(task.id, &values_to_vec(matches, "untag"), conn)?;
+            let updated_tags = get_tags_for_task(task.id, conn)?;
+            if existing_tags != updated_tags {
+                let event = AddTaskEvent::new(
+                    task.id,
+                    "tags",
+                    Some(existing_tags.join(", ")),
+                    Some(updated_tags.join(", ")),
+                    now,
+                );
+                record_events(&[event], conn)?;
+            }
 
-        diesel::update(suaide.find(task.id))
-            .set(change_set)
-            .execute(state.get_conn())?;
+            use crate::schema::suaide::dsl::{id, suaide};
 
-        let task = suaide
-            .filter(id.eq(task.id))
-            .limit(1)
-            .load::<Task>(state.get_conn())?
-            .pop();
+            let updated = suaide
+                .filter(id.eq(task.id))
+                .limit(1)
+                .load::<Task>(conn)?
+                .pop();
 
-        if let Some(task) = task {
-            task.print(is_verbose);
+            if let Some(updated) = &updated {
+                // Tags are journaled too, so an edit that only changes them can
+                // still be undone
+                record_snapshots(
+                    "edit",
+                    &[TaskSnapshot::with_tags(task.clone(), existing_tags)],
+                    &[TaskSnapshot::with_tags(updated.clone(), updated_tags)],
+                    now,
+                    conn,
+                )?;
+            }
+            Ok(updated)
+        })?;
+
+        if let Some(updated) = updated {
+            let output = get_task_output(&updated, state.get_conn())?;
+            TaskRenderer::new(state.get_output_format(), is_verbose)
+                .render_task(state.writer(), &output)?;
         }
         return Ok(());
     }
@@ -63,11 +143,18 @@ fn grab_input_from_user<W: io::Write>(
     let description = state.get_input("description", Some(task.description.clone()))?;
     let ticket_id = state.get_optional_input("ID", task.ticket.clone())?;
     let ticket = state.generate_ticket_id(ticket_id);
-    let status = get_state_input(task.status.into());
+    let status = get_state_input(state, task.status.into())?;
+    if status == Status::Closed && task.task_status() != Status::Closed {
+        confirm_closing_parent(state, task, &[], false)?;
+    }
 
     change_set.set_description(task, description);
     change_set.set_ticket(task, ticket);
-    change_set.set_status(task, status);
+    change_set.set_status(task, status, state.now().timestamp());
+    if status == Status::Blocked {
+        let reason = get_blocked_reason(state, None, task.blocked_reason.clone())?;
+        change_set.set_blocked_reason(task, Some(reason));
+    }
     Ok(change_set)
 }
 
@@ -75,7 +162,9 @@ fn grab_input_from_user<W: io::Write>(
 mod test_edit_app {
     use super::*;
 
-    use crate::domain::{Status, Task};
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::testing;
+    use crate::domain::Task;
     use crate::schema::suaide::dsl::*;
     use crate::state::State;
 
@@ -84,7 +173,16 @@ mod test_edit_app {
         let mut writer = Vec::new();
         let mut state = State::new(&mut writer).unwrap();
 
-        test_helpers::insert_task(state.get_conn());
+        testing::task()
+            .ticket("1234")
+            .status(Status::Cancelled)
+            .insert(state.get_conn());
+        state.set_prompter(Box::new(
+            ScriptedPrompter::default()
+                .input("Edited Description")
+                .input("5678")
+                .select("In Progress"),
+        ));
 
         let matches = app().get_matches_from(vec!["edit", "1234"]);
         let result = handler(&matches, &mut state);
@@ -97,29 +195,88 @@ mod test_edit_app {
             .expect("This should return an Ok");
 
         assert_eq!(result.id, 1);
-        assert_eq!(result.ticket, Some("MOCK DATA".to_string()));
-        assert_eq!(result.description, "MOCK DATA".to_string());
-        assert_eq!(result.status, Status::Cancelled as i16);
+        assert_eq!(result.ticket, Some("5678".to_string()));
+        assert_eq!(result.description, "Edited Description".to_string());
+        assert_eq!(result.status, Status::InProgress as i16);
         assert_eq!(result.closed, None);
     }
-}
 
-#[cfg(test)]
-mod test_helpers {
-    use crate::domain::AddTask;
-    use diesel::prelude::*;
-
-    pub fn insert_task(db_conn: &SqliteConnection) {
-        let task = AddTask {
-            ticket: Some("1234".to_string()),
-            description: "Test Description".to_string(),
-            status: 3,
-            opened: 10000,
-        };
+    #[test]
+    fn should_close_a_task_when_completed() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .status(Status::Cancelled)
+            .insert(state.get_conn());
+        state.set_prompter(Box::new(
+            ScriptedPrompter::default()
+                .input("Test Description")
+                .input("1234")
+                .select("Completed"),
+        ));
+
+        let matches = app().get_matches_from(vec!["edit", "1234"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+
+        assert_eq!(result.ticket, Some("1234".to_string()));
+        assert_eq!(result.status, Status::Closed as i16);
+        assert!(result.closed.is_some());
+    }
+
+    #[test]
+    fn should_set_and_clear_the_due_date() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .status(Status::Cancelled)
+            .insert(state.get_conn());
+        state.set_prompter(Box::new(ScriptedPrompter::default()));
+
+        let matches = app().get_matches_from(vec!["edit", "1234", "--due", "2020-10-09"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert!(result.due.is_some());
+
+        let matches = app().get_matches_from(vec!["edit", "1234", "--due", "none"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.due, None);
+    }
+
+    #[test]
+    fn should_add_and_remove_tags() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .status(Status::Cancelled)
+            .insert(state.get_conn());
+        state.set_prompter(Box::new(ScriptedPrompter::default()));
+
+        let matches = app().get_matches_from(vec!["edit", "1234", "--tag", "backend,review"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["edit", "1234", "--untag", "review"]);
+        assert!(handler(&matches, &mut state).is_ok());
 
-        diesel::insert_into(crate::schema::suaide::table)
-            .values(task)
-            .execute(db_conn)
-            .expect("Insert should be successful");
+        let tags = get_tags_for_task(1, state.get_conn()).expect("This should return an Ok");
+        assert_eq!(tags, vec!["backend".to_string()]);
     }
 }
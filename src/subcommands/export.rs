@@ -0,0 +1,403 @@
+use clap::{App, Arg, ArgMatches};
+use colored::Colorize;
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use diesel::prelude::*;
+
+use crate::common::args::{
+    duration_arg, values_to_vec, window_from_matches, STATUS_FILTERS, TIMEFRAMES,
+};
+use crate::common::render::{write_json, TaskRenderer};
+use crate::common::storage::{
+    apply_deleted_filter, apply_project_filter, apply_status_filter, assign_uuids,
+    get_task_outputs, window_query,
+};
+use crate::domain::{ExportRow, Status, SuaideError, Task, TaskwarriorTask};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("export")
+        .about("Export your tasks to CSV, JSON, Markdown or Taskwarrior")
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .help("The format to export the tasks in, ndjson writes a JSON object per line and taskwarrior writes what task import reads")
+                .long_help("The format to export the tasks in, ndjson writes a JSON object per line and taskwarrior writes what task import reads.\nA taskwarrior export gives every task without a uuid a new one and stores it, so the task keeps it in later exports\n")
+                .possible_values(&["csv", "json", "markdown", "ndjson", "taskwarrior"])
+                .default_value("csv")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("file")
+                .long("file")
+                .short("o")
+                .help("The file to export to, otherwise the tasks are written to stdout")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("timeframe")
+                .long("timeframe")
+                .short("t")
+                .help("Only export tasks worked on, or due, within this timeframe")
+                .conflicts_with("duration")
+                .default_value("all")
+                .possible_values(&TIMEFRAMES)
+                .takes_value(true),
+        )
+        .arg(duration_arg())
+        .arg(
+            Arg::with_name("status")
+                .long("status")
+                .short("s")
+                .help("Only export tasks with this status, can be repeated or comma separated")
+                .multiple(true)
+                .number_of_values(1)
+                .use_delimiter(true)
+                .possible_values(&STATUS_FILTERS)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("all_projects")
+                .long("all-projects")
+                .help("Export tasks from every project, not just the active one"),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let statuses: Vec<Status> = values_to_vec(matches, "status")
+        .iter()
+        .flat_map(|s| Status::from_filter(s))
+        .collect();
+    let project = if matches.is_present("all_projects") {
+        None
+    } else {
+        state.get_project().map(|p| p.id)
+    };
+    let (start, end, by_due_date) = window_from_matches(matches, state.now())?;
+
+    use crate::schema::suaide::dsl::id;
+
+    let query = apply_deleted_filter(window_query(start, end, by_due_date));
+    let query = apply_project_filter(query, project);
+    let tasks = apply_status_filter(query, &statuses)
+        .order_by(id.asc())
+        .load::<Task>(state.get_conn())?;
+    let outputs = get_task_outputs(&tasks, state.get_conn())?;
+
+    let format = matches.value_of("format").expect("has default value");
+    let export = if format == "taskwarrior" {
+        let uuids = assign_uuids(&tasks, state.get_conn())?;
+        Export::Taskwarrior(
+            uuids
+                .into_iter()
+                .zip(outputs.iter())
+                .map(|(uuid, task)| TaskwarriorTask::from_output(uuid, task))
+                .collect(),
+        )
+    } else {
+        Export::Rows(outputs.iter().map(ExportRow::from).collect())
+    };
+    match matches.value_of("file") {
+        Some(path) => {
+            let mut file = BufWriter::new(File::create(path)?);
+            write_export(&mut file, format, &export)?;
+            file.flush()?;
+            let message = format!("{} {} task(s) to {}", "Exported".green(), tasks.len(), path);
+            TaskRenderer::new(state.get_output_format(), false).render_message(
+                state.writer(),
+                &json!({ "exported": tasks.len(), "file": path }),
+                &message,
+            )
+        }
+        None => write_export(state.writer(), format, &export),
+    }
+}
+
+/// Taskwarrior has its own fields, every other format writes the same rows
+enum Export {
+    Rows(Vec<ExportRow>),
+    Taskwarrior(Vec<TaskwarriorTask>),
+}
+
+fn write_export<W: io::Write>(
+    writer: &mut W,
+    format: &str,
+    export: &Export,
+) -> Result<(), SuaideError> {
+    let rows = match export {
+        Export::Rows(rows) => rows,
+        Export::Taskwarrior(tasks) => return write_json(writer, tasks),
+    };
+    match format {
+        "json" => write_json(writer, &rows),
+        "ndjson" => {
+            for row in rows {
+                write_json(writer, row)?;
+            }
+            Ok(())
+        }
+        "markdown" => write_markdown(writer, rows),
+        _ => write_csv(writer, rows),
+    }
+}
+
+fn write_csv<W: io::Write>(writer: &mut W, rows: &[ExportRow]) -> Result<(), SuaideError> {
+    writeln!(writer, "{}", ExportRow::COLUMNS.join(","))?;
+    for row in rows {
+        let record: Vec<String> = row.to_record().iter().map(|f| csv_field(f)).collect();
+        writeln!(writer, "{}", record.join(","))?;
+    }
+    Ok(())
+}
+
+/// Fields are only quoted when they have to be, quotes within them are doubled
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A table meant for people to read, so only local times are included
+fn write_markdown<W: io::Write>(writer: &mut W, rows: &[ExportRow]) -> Result<(), SuaideError> {
+    writeln!(
+        writer,
+        "| Ticket | Description | Status | Project | Tags | Priority | Opened | Closed | Due |"
+    )?;
+    writeln!(
+        writer,
+        "| --- | --- | --- | --- | --- | --- | --- | --- | --- |"
+    )?;
+    for row in rows {
+        let ticket = match &row.ticket {
+            Some(ticket) => ticket.clone(),
+            None => format!("#{}", row.id),
+        };
+        let cells = [
+            ticket,
+            row.description.clone(),
+            row.status.name().to_string(),
+            row.project.clone().unwrap_or_default(),
+            row.tags.join(", "),
+            row.priority
+                .map(|p| p.name().to_string())
+                .unwrap_or_default(),
+            row.opened_local.clone(),
+            row.closed_local.clone().unwrap_or_default(),
+            row.due_local.clone().unwrap_or_default(),
+        ];
+        let cells: Vec<String> = cells.iter().map(|c| markdown_cell(c)).collect();
+        writeln!(writer, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod test_export_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::storage::{add_note_to_task, add_tags_to_task};
+    use crate::common::testing;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn exports_csv_with_every_column() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .description("Fix login, then logout")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Say \"hi\"")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        add_tags_to_task(
+            1,
+            &["backend".to_string(), "review".to_string()],
+            state.get_conn(),
+        )
+        .unwrap();
+
+        let matches = app().get_matches_from(vec!["export"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let mut lines = data.lines();
+        assert_eq!(
+            lines.next(),
+            Some("id,ticket,description,status,project,tags,priority,parent_id,blocked_reason,opened,opened_local,closed,closed_local,due,due_local")
+        );
+        let first = lines.next().unwrap();
+        assert!(first.starts_with(
+            "1,1234,\"Fix login, then logout\",open,,\"backend,review\",,,,1970-01-01T02:46:40Z,"
+        ));
+        let second = lines.next().unwrap();
+        assert!(second.starts_with("2,,\"Say \"\"hi\"\"\",in-progress,"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn exports_json_and_ndjson() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Other Description")
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["export", "--format", "json"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let matches = app().get_matches_from(vec!["export", "-f", "ndjson"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let mut lines = data.lines();
+        let output: serde_json::Value =
+            serde_json::from_str(lines.next().unwrap()).expect("should be valid JSON");
+        assert_eq!(output.as_array().map(Vec::len), Some(2));
+        assert_eq!(output[0]["ticket"], "1234");
+        assert_eq!(output[0]["status"], "in-progress");
+        assert_eq!(output[0]["opened"], "1970-01-01T02:46:40Z");
+        assert!(output[0]["opened_local"].is_string());
+        assert_eq!(output[0]["closed"], serde_json::Value::Null);
+
+        for expected in &[1, 2] {
+            let row: serde_json::Value =
+                serde_json::from_str(lines.next().unwrap()).expect("should be valid JSON");
+            assert_eq!(row["id"], *expected);
+        }
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn exports_a_markdown_table() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .description("Pipe | separated")
+            .status(Status::Closed)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["export", "--format", "markdown"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("| Ticket | Description | Status |"));
+        assert!(lines[2].starts_with("| 1234 | Pipe \\| separated | Completed |"));
+    }
+
+    #[test]
+    fn exports_the_timeframe_to_a_file() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        testing::task()
+            .ticket("OLD")
+            .description("Old task")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("NEW")
+            .description("New task")
+            .insert(state.get_conn());
+        use crate::schema::suaide::dsl::{opened, suaide};
+        diesel::update(suaide.find(2))
+            .set(opened.eq(now.timestamp()))
+            .execute(state.get_conn())
+            .unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("suaide-export-{}.ndjson", std::process::id()));
+        let path_arg = path.to_str().unwrap();
+        let matches = app().get_matches_from(vec![
+            "export", "-f", "ndjson", "-t", "today", "-o", path_arg,
+        ]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let exported = std::fs::read_to_string(&path).expect("the file should be written");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(exported.lines().count(), 1);
+        assert!(exported.contains("\"ticket\":\"NEW\""));
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.ends_with(&format!(" 1 task(s) to {}\n", path_arg)));
+    }
+
+    #[test]
+    fn exports_taskwarrior_json_with_stable_uuids() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .description("Open task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Closed task")
+            .status(Status::Closed)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Cancelled task")
+            .status(Status::Cancelled)
+            .insert(state.get_conn());
+        use crate::schema::suaide::dsl::{closed, priority, suaide};
+        diesel::update(suaide.find(2))
+            .set((closed.eq(Some(20000)), priority.eq(Some(0))))
+            .execute(state.get_conn())
+            .unwrap();
+        add_tags_to_task(1, &["backend".to_string()], state.get_conn()).unwrap();
+        add_note_to_task(1, "Started".to_string(), 15000, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["export", "-f", "taskwarrior"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let mut exports = data.lines().map(|line| {
+            serde_json::from_str::<Vec<TaskwarriorTask>>(line).expect("should be valid json")
+        });
+        let first = exports.next().unwrap();
+        assert_eq!(first, exports.next().unwrap());
+
+        assert!(first.iter().all(|task| task.uuid.is_some()));
+        let statuses: Vec<&str> = first.iter().map(|task| task.status.as_str()).collect();
+        assert_eq!(statuses, vec!["pending", "completed", "deleted"]);
+        assert_eq!(first[0].entry, Some("19700101T024640Z".to_string()));
+        assert_eq!(first[0].tags, vec!["backend".to_string()]);
+        assert_eq!(first[0].annotations[0].description, "Started");
+        assert_eq!(
+            first[0].annotations[0].entry,
+            Some("19700101T041000Z".to_string())
+        );
+        assert_eq!(first[1].end, Some("19700101T053320Z".to_string()));
+        assert_eq!(first[1].priority, Some("H".to_string()));
+    }
+}
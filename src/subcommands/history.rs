@@ -0,0 +1,156 @@
+use clap::{App, Arg, ArgMatches};
+use colored::Colorize;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::get_events_for_task;
+use crate::domain::{SuaideError, TaskEvent, TaskEventOutput, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("history")
+        .about("Show every change made to a task")
+        .arg(
+            Arg::with_name("task")
+                .index(1)
+                .required(true)
+                .help("The task to show the history of")
+                .takes_value(true),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    if let Some(task) = matches.value_of("task") {
+        let task = state.find_task(task)?;
+        let events: Vec<TaskEventOutput> = get_events_for_task(task.id, state.get_conn())?
+            .iter()
+            .map(TaskEventOutput::from)
+            .collect();
+
+        let mut message = vec![format!(
+            "{} - {}",
+            TaskOutput::reference(&task).bold(),
+            task.description
+        )];
+        for event in &events {
+            let change = match (&event.old_value, &event.new_value) {
+                (None, Some(new)) if event.field == TaskEvent::STATUS => {
+                    format!("added as {}", new)
+                }
+                (old, new) => format!(
+                    "{} -> {}",
+                    old.as_deref().unwrap_or("none"),
+                    new.as_deref().unwrap_or("none")
+                ),
+            };
+            message.push(format!(
+                "{}\t{}: {}",
+                event.created.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+                event.field,
+                change
+            ));
+        }
+        return TaskRenderer::new(state.get_output_format(), false).render_message(
+            state.writer(),
+            &events,
+            &message.join("\n"),
+        );
+    }
+    Err(SuaideError::IncorrectArgs)
+}
+
+#[cfg(test)]
+mod test_history_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::storage::{get_task_by_id, record_events, update_task};
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::domain::{AddTaskEvent, Priority, Status, TaskChangeSet};
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    fn build_history(db_conn: &diesel::SqliteConnection) {
+        let added = Local.ymd(2020, 11, 23).and_hms(9, 0, 0).timestamp();
+        let started = Local.ymd(2020, 11, 23).and_hms(10, 30, 0).timestamp();
+
+        testing::task().ticket("1234").insert(db_conn);
+        let task = get_task_by_id(1, db_conn).unwrap();
+        record_events(&[AddTaskEvent::added(&task, added)], db_conn).unwrap();
+
+        let mut change_set = TaskChangeSet::default();
+        change_set.set_status(&task, Status::InProgress, started);
+        change_set.set_priority(&task, Some(Priority::P1));
+        update_task(&task, change_set, started, db_conn).unwrap();
+    }
+
+    #[test]
+    fn shows_the_timeline() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        build_history(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["history", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("Test Description"));
+        assert!(lines[1].contains("2020-11-23 09:00"));
+        assert!(lines[1].ends_with("status: added as Open"));
+        assert!(lines[2].contains("2020-11-23 10:30"));
+        assert!(lines[2].ends_with("status: Open -> In Progress"));
+        assert!(lines[3].ends_with("priority: none -> P1"));
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        build_history(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["history", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output[1]["field"], "status");
+        assert_eq!(output[1]["old_value"], "Open");
+        assert_eq!(output[1]["new_value"], "In Progress");
+        assert!(output[1]["created"].is_string());
+    }
+
+    #[test]
+    fn stores_statuses_by_key() {
+        let mut writer = Vec::new();
+        let state = State::new(&mut writer).unwrap();
+
+        build_history(state.get_conn());
+        let events = get_events_for_task(1, state.get_conn()).unwrap();
+        assert_eq!(events[1].old_value.as_deref(), Some("open"));
+        assert_eq!(events[1].new_value.as_deref(), Some("in-progress"));
+        assert_eq!(events[1].new_status(), Some(Status::InProgress));
+    }
+
+    #[test]
+    fn should_error_with_not_found() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["history", "1234"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NotFound)
+        ));
+    }
+}
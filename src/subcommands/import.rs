@@ -0,0 +1,1121 @@
+use chrono::prelude::*;
+use clap::{App, Arg, ArgMatches};
+use colored::Colorize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use diesel::prelude::*;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    add_note_to_task, add_project, add_tags_to_task, get_last_inserted_task, get_notes_for_task,
+    get_project, get_tags_for_task, get_task_by_id, record_events, record_journal, update_task,
+};
+use crate::common::time::{end_of_day, local_datetime, parse_date};
+use crate::common::DATE_FORMAT;
+use crate::domain::{
+    AddProject, AddTask, AddTaskEvent, Priority, Status, SuaideError, Tag, Task, TaskChangeSet,
+    TaskwarriorTask,
+};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("import")
+        .about("Import tasks from a CSV or JSON file, such as one written by export")
+        .arg(
+            Arg::with_name("file")
+                .index(1)
+                .required(true)
+                .help("The file to import")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .help("The format of the file, otherwise it's worked out from the file extension, taskwarrior reads the JSON written by task export")
+                .possible_values(&["csv", "json", "ndjson", "taskwarrior"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("on_conflict")
+                .long("on-conflict")
+                .help("What to do with a task whose ticket already exists")
+                .possible_values(&["skip", "update", "fail"])
+                .default_value("fail")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .short("n")
+                .help("Show what would change without changing anything"),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let path = matches.value_of("file").ok_or(SuaideError::IncorrectArgs)?;
+    let format = match matches.value_of("format") {
+        Some(format) => format,
+        None => match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("csv") => "csv",
+            Some("json") => "json",
+            Some("ndjson") | Some("jsonl") => "ndjson",
+            _ => return Err(SuaideError::IncorrectArgs),
+        },
+    };
+    let on_conflict = OnConflict::from(matches.value_of("on_conflict").expect("has default value"));
+    let is_dry_run = matches.is_present("dry_run");
+
+    let contents = fs::read_to_string(path)?;
+    let rows = match format {
+        "csv" => rows_from_csv(&contents)?,
+        "taskwarrior" => json_records::<TaskwarriorTask>(&contents)?
+            .into_iter()
+            .map(ImportRow::from)
+            .collect(),
+        _ => json_records(&contents)?,
+    };
+    let now = state.now().timestamp();
+    let tasks = rows
+        .into_iter()
+        .enumerate()
+        .map(|(idx, row)| ImportTask::from_row(row, idx + 1, now))
+        .collect::<Result<Vec<ImportTask>, SuaideError>>()?;
+
+    let actions = plan_import(tasks, on_conflict, now, state.get_conn())?;
+    if !is_dry_run {
+        apply_import(&actions, now, state.get_conn())?;
+    }
+    write_summary(state, &actions, is_dry_run)
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum OnConflict {
+    Skip,
+    Update,
+    Fail,
+}
+
+impl From<&str> for OnConflict {
+    fn from(s: &str) -> Self {
+        match s {
+            "skip" => OnConflict::Skip,
+            "update" => OnConflict::Update,
+            "fail" => OnConflict::Fail,
+            _ => panic!("unable to convert argument to a conflict resolution"),
+        }
+    }
+}
+
+/// A row as it's read from the file, every column an export writes is accepted
+/// although ids and local times are ignored
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct ImportRow {
+    ticket: Option<String>,
+    description: Option<String>,
+    status: Option<String>,
+    project: Option<String>,
+    tags: Vec<String>,
+    priority: Option<String>,
+    blocked_reason: Option<String>,
+    opened: Option<String>,
+    closed: Option<String>,
+    due: Option<String>,
+    uuid: Option<String>,
+    #[serde(skip)]
+    notes: Vec<ImportNote>,
+}
+
+#[derive(Debug)]
+struct ImportNote {
+    body: String,
+    created: Option<String>,
+}
+
+impl From<TaskwarriorTask> for ImportRow {
+    fn from(task: TaskwarriorTask) -> Self {
+        // Fields suaide wrote itself are used over the closest Taskwarrior
+        // ones, and anything that isn't a Taskwarrior status is left to fail as
+        // a suaide one
+        let status = match (
+            task.suaide_status,
+            TaskwarriorTask::parse_status(&task.status),
+        ) {
+            (Some(status), _) => status,
+            (None, Some(status)) => status.key().to_string(),
+            (None, None) => task.status,
+        };
+        let priority = match (task.suaide_priority, task.priority) {
+            (Some(priority), _) => Some(priority),
+            (None, Some(priority)) => match TaskwarriorTask::parse_priority(&priority) {
+                Some(priority) => Some(priority.name().to_string()),
+                None => Some(priority),
+            },
+            (None, None) => None,
+        };
+        Self {
+            ticket: task.suaide_ticket,
+            description: Some(task.description),
+            status: Some(status),
+            project: task.project,
+            tags: task.tags,
+            priority,
+            blocked_reason: task.suaide_blocked_reason,
+            opened: task.entry,
+            closed: task.end,
+            due: task.due,
+            uuid: task.uuid,
+            notes: task
+                .annotations
+                .into_iter()
+                .map(|annotation| ImportNote {
+                    body: annotation.description,
+                    created: annotation.entry,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A row once it has been checked and converted into what's stored
+#[derive(Debug)]
+struct ImportTask {
+    ticket: Option<String>,
+    description: String,
+    status: Status,
+    project: Option<String>,
+    tags: Vec<String>,
+    priority: Option<Priority>,
+    blocked_reason: Option<String>,
+    opened: i64,
+    closed: Option<i64>,
+    due: Option<i64>,
+    uuid: Option<String>,
+    notes: Vec<(String, i64)>,
+}
+
+impl ImportTask {
+    fn from_row(row: ImportRow, number: usize, now: i64) -> Result<Self, SuaideError> {
+        let error = |message: String| SuaideError::ImportRowError(number, message);
+        let description = row
+            .description
+            .filter(|d| !d.trim().is_empty())
+            .ok_or_else(|| error("a description is required".to_string()))?;
+        let status = match row.status.as_deref() {
+            Some(status) => status
+                .parse()
+                .map_err(|_| error(format!("unknown status {}", status)))?,
+            None => Status::Open,
+        };
+        let priority = match row.priority.as_deref() {
+            Some(priority) => Some(
+                priority
+                    .parse()
+                    .map_err(|_| error(format!("unknown priority {}", priority)))?,
+            ),
+            None => None,
+        };
+        let timestamp = |value: Option<String>, due: bool| match value {
+            Some(value) => parse_timestamp(&value, due)
+                .map(Some)
+                .ok_or_else(|| error(format!("invalid date {}", value))),
+            None => Ok(None),
+        };
+        let opened = timestamp(row.opened, false)?.unwrap_or(now);
+        let closed = match status {
+            Status::Closed | Status::Cancelled => {
+                Some(timestamp(row.closed, false)?.unwrap_or(now))
+            }
+            _ => None,
+        };
+        let due = timestamp(row.due, true)?;
+        let blocked_reason = match status {
+            Status::Blocked => Some(
+                row.blocked_reason
+                    .filter(|r| !r.trim().is_empty())
+                    .ok_or_else(|| error("a blocked task needs a blocked_reason".to_string()))?,
+            ),
+            _ => None,
+        };
+        let notes = row
+            .notes
+            .into_iter()
+            .map(|note| Ok((note.body, timestamp(note.created, false)?.unwrap_or(opened))))
+            .collect::<Result<Vec<(String, i64)>, SuaideError>>()?;
+
+        Ok(Self {
+            ticket: row.ticket.filter(|t| !t.trim().is_empty()),
+            description,
+            status,
+            project: row.project.filter(|p| !p.trim().is_empty()),
+            tags: row.tags,
+            priority,
+            blocked_reason,
+            opened,
+            closed,
+            due,
+            uuid: row.uuid.filter(|u| !u.trim().is_empty()),
+            notes,
+        })
+    }
+
+    /// How the task is referred to in the summary
+    fn reference(&self) -> String {
+        match &self.ticket {
+            Some(ticket) => ticket.clone(),
+            None => self.description.clone(),
+        }
+    }
+
+    /// The ways an existing task, or an earlier row, can be the same task
+    fn keys(&self) -> Vec<String> {
+        let uuid = self.uuid.iter().map(|uuid| format!("uuid:{}", uuid));
+        let ticket = self
+            .ticket
+            .iter()
+            .map(|ticket| format!("ticket:{}", ticket));
+        uuid.chain(ticket).collect()
+    }
+}
+
+/// Accepts ISO-8601 and Taskwarrior timestamps as well as the local times and
+/// dates used elsewhere, a due date without a time is due by the end of the day
+fn parse_timestamp(value: &str, due: bool) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.timestamp());
+    }
+    if let Ok(date) = Local.datetime_from_str(value, DATE_FORMAT) {
+        return Some(date.timestamp());
+    }
+    if let Some(date) = TaskwarriorTask::parse_date(value) {
+        return Some(date.timestamp());
+    }
+    let date = parse_date(value).ok()?;
+    if due {
+        end_of_day(date).ok()
+    } else {
+        local_datetime(date.and_hms(0, 0, 0)).map(|time| time.timestamp())
+    }
+}
+
+/// Reads either a JSON array or a JSON object per line
+fn json_records<T: DeserializeOwned>(contents: &str) -> Result<Vec<T>, SuaideError> {
+    if contents.trim_start().starts_with('[') {
+        let values: Vec<serde_json::Value> = serde_json::from_str(contents)?;
+        return values
+            .into_iter()
+            .enumerate()
+            .map(|(idx, value)| {
+                serde_json::from_value(value)
+                    .map_err(|e| SuaideError::ImportRowError(idx + 1, e.to_string()))
+            })
+            .collect();
+    }
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| SuaideError::ImportRowError(idx + 1, e.to_string()))
+        })
+        .collect()
+}
+
+fn rows_from_csv(contents: &str) -> Result<Vec<ImportRow>, SuaideError> {
+    let mut records = parse_csv(contents)?.into_iter();
+    let header: HashMap<String, usize> = match records.next() {
+        Some(header) => header
+            .into_iter()
+            .enumerate()
+            .map(|(idx, column)| (column.trim().to_lowercase(), idx))
+            .collect(),
+        None => return Ok(Vec::new()),
+    };
+    let rows = records
+        .filter(|record| record.iter().any(|field| !field.is_empty()))
+        .map(|record| {
+            let field = |column: &str| {
+                header
+                    .get(column)
+                    .and_then(|idx| record.get(*idx))
+                    .filter(|value| !value.is_empty())
+                    .cloned()
+            };
+            ImportRow {
+                ticket: field("ticket"),
+                description: field("description"),
+                status: field("status"),
+                project: field("project"),
+                tags: field("tags")
+                    .map(|tags| {
+                        tags.split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                priority: field("priority"),
+                blocked_reason: field("blocked_reason"),
+                opened: field("opened"),
+                closed: field("closed"),
+                due: field("due"),
+                uuid: field("uuid"),
+                notes: Vec::new(),
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Splits CSV into records of fields, quoted fields can contain commas, line
+/// breaks and doubled quotes
+fn parse_csv(contents: &str) -> Result<Vec<Vec<String>>, SuaideError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(SuaideError::ImportRowError(
+            records.len(),
+            "a quoted field is never closed".to_string(),
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+enum ImportAction {
+    Add(ImportTask),
+    Update(Box<Task>, ImportTask, Vec<String>),
+    Skip(ImportTask),
+}
+
+/// Works out what importing each task would do without changing anything.
+/// Tasks are matched by uuid and then by ticket, a task that's repeated within
+/// the file conflicts with its earlier row
+fn plan_import(
+    tasks: Vec<ImportTask>,
+    on_conflict: OnConflict,
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<ImportAction>, SuaideError> {
+    let mut actions: Vec<ImportAction> = Vec::new();
+    let mut planned: HashMap<String, usize> = HashMap::new();
+    for task in tasks {
+        let keys = task.keys();
+        if keys.is_empty() {
+            actions.push(ImportAction::Add(task));
+            continue;
+        }
+        let existing = find_existing(&task, db_conn)?;
+        let earlier = keys.iter().find_map(|key| planned.get(key).copied());
+        if existing.is_none() && earlier.is_none() {
+            for key in keys {
+                planned.insert(key, actions.len());
+            }
+            actions.push(ImportAction::Add(task));
+            continue;
+        }
+        match on_conflict {
+            OnConflict::Fail => {
+                let identity = task.ticket.or(task.uuid).expect("has a key");
+                return Err(SuaideError::ImportConflictError(identity));
+            }
+            OnConflict::Skip => actions.push(ImportAction::Skip(task)),
+            OnConflict::Update => match (earlier, existing) {
+                (Some(idx), _) => match &mut actions[idx] {
+                    ImportAction::Add(earlier) | ImportAction::Update(_, earlier, _) => {
+                        *earlier = task
+                    }
+                    ImportAction::Skip(_) => unreachable!("skipped tasks aren't planned"),
+                },
+                (None, Some(existing)) => {
+                    for key in keys {
+                        planned.insert(key, actions.len());
+                    }
+                    actions.push(ImportAction::Update(Box::new(existing), task, Vec::new()));
+                }
+                (None, None) => unreachable!("either planned or existing"),
+            },
+        }
+    }
+
+    // An update that wouldn't change anything is the same as skipping it
+    actions
+        .into_iter()
+        .map(|action| match action {
+            ImportAction::Update(existing, task, _) => {
+                let mut fields: Vec<String> = change_set(&existing, &task, now)
+                    .events(&existing, now)
+                    .into_iter()
+                    .map(|event| event.field)
+                    .collect();
+                let tags = get_tags_for_task(existing.id, db_conn)?;
+                if task
+                    .tags
+                    .iter()
+                    .any(|tag| !tags.contains(&Tag::normalize(tag)))
+                {
+                    fields.push("tags".to_string());
+                }
+                if !new_notes(existing.id, &task, db_conn)?.is_empty() {
+                    fields.push("notes".to_string());
+                }
+                if fields.is_empty() {
+                    Ok(ImportAction::Skip(task))
+                } else {
+                    Ok(ImportAction::Update(existing, task, fields))
+                }
+            }
+            action => Ok(action),
+        })
+        .collect()
+}
+
+/// Tasks in the trash still hold on to their uuid and ticket
+fn find_existing(
+    task: &ImportTask,
+    db_conn: &SqliteConnection,
+) -> Result<Option<Task>, SuaideError> {
+    use crate::schema::suaide::dsl::{suaide, ticket, uuid};
+
+    if let Some(task_uuid) = &task.uuid {
+        let existing = suaide
+            .filter(uuid.eq(task_uuid))
+            .first::<Task>(db_conn)
+            .optional()?;
+        if existing.is_some() {
+            return Ok(existing);
+        }
+    }
+    match &task.ticket {
+        Some(task_ticket) => Ok(suaide
+            .filter(ticket.eq(task_ticket))
+            .first::<Task>(db_conn)
+            .optional()?),
+        None => Ok(None),
+    }
+}
+
+/// The notes being imported that the task doesn't already have
+fn new_notes<'a>(
+    task_id: i32,
+    task: &'a ImportTask,
+    db_conn: &SqliteConnection,
+) -> Result<Vec<&'a (String, i64)>, SuaideError> {
+    let notes = get_notes_for_task(task_id, db_conn)?;
+    Ok(task
+        .notes
+        .iter()
+        .filter(|(body, _)| !notes.iter().any(|note| &note.body == body))
+        .collect())
+}
+
+fn change_set(existing: &Task, task: &ImportTask, now: i64) -> TaskChangeSet {
+    let mut change_set = TaskChangeSet::default();
+    change_set.set_description(existing, task.description.clone());
+    change_set.set_status(existing, task.status, now);
+    change_set.set_closed(existing, task.closed);
+    change_set.set_blocked_reason(existing, task.blocked_reason.clone());
+    change_set.set_priority(existing, task.priority);
+    change_set.set_due(existing, task.due);
+    // Updating a task from the trash brings it back
+    change_set.set_deleted(existing, None);
+    change_set
+}
+
+/// Applies every action in a single transaction, so either the whole file is
+/// imported or nothing is
+fn apply_import(
+    actions: &[ImportAction],
+    now: i64,
+    db_conn: &SqliteConnection,
+) -> Result<(), SuaideError> {
+    db_conn.transaction::<_, SuaideError, _>(|| {
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        for action in actions {
+            match action {
+                ImportAction::Add(task) => {
+                    let project_id = match &task.project {
+                        Some(name) => Some(get_or_add_project(name, db_conn)?),
+                        None => None,
+                    };
+                    let add = AddTask {
+                        ticket: task.ticket.clone(),
+                        description: task.description.clone(),
+                        opened: task.opened,
+                        status: task.status as i16,
+                        closed: task.closed,
+                        project_id,
+                        blocked_reason: task.blocked_reason.clone(),
+                        due: task.due,
+                        priority: task.priority.map(|p| p as i16),
+                        parent_id: None,
+                        uuid: task.uuid.clone(),
+                    };
+                    diesel::insert_into(crate::schema::suaide::table)
+                        .values(&add)
+                        .execute(db_conn)?;
+                    let inserted = get_last_inserted_task(db_conn)?;
+                    add_tags_to_task(inserted.id, &task.tags, db_conn)?;
+                    for (body, created) in &task.notes {
+                        add_note_to_task(inserted.id, body.clone(), *created, db_conn)?;
+                    }
+                    record_events(&[AddTaskEvent::added(&inserted, now)], db_conn)?;
+                    after.push(inserted);
+                }
+                ImportAction::Update(existing, task, _) => {
+                    before.push(get_task_by_id(existing.id, db_conn)?);
+                    update_task(existing, change_set(existing, task, now), now, db_conn)?;
+                    add_tags_to_task(existing.id, &task.tags, db_conn)?;
+                    for (body, created) in new_notes(existing.id, task, db_conn)? {
+                        add_note_to_task(existing.id, body.clone(), *created, db_conn)?;
+                    }
+                    after.push(get_task_by_id(existing.id, db_conn)?);
+                }
+                ImportAction::Skip(_) => {}
+            }
+        }
+        record_journal("import", &before, &after, now, db_conn)
+    })
+}
+
+fn get_or_add_project(name: &str, db_conn: &SqliteConnection) -> Result<i32, SuaideError> {
+    match get_project(name, db_conn) {
+        Ok(project) => Ok(project.id),
+        Err(SuaideError::ProjectNotFound) => {
+            let project = AddProject {
+                name: name.to_string(),
+                prefix: String::new(),
+            };
+            Ok(add_project(&project, db_conn)?.id)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+struct ImportSummary {
+    dry_run: bool,
+    added: Vec<String>,
+    updated: Vec<String>,
+    skipped: Vec<String>,
+}
+
+fn write_summary<W: io::Write>(
+    state: &mut State<W>,
+    actions: &[ImportAction],
+    is_dry_run: bool,
+) -> Result<(), SuaideError> {
+    let mut summary = ImportSummary {
+        dry_run: is_dry_run,
+        ..ImportSummary::default()
+    };
+    let mut message = Vec::new();
+    let (add, update, skip) = if is_dry_run {
+        ("Would add", "Would update", "Would skip")
+    } else {
+        ("Added", "Updated", "Skipped")
+    };
+    for action in actions {
+        match action {
+            ImportAction::Add(task) => {
+                message.push(format!("[{}]: {}", add.green(), task.reference()));
+                summary.added.push(task.reference());
+            }
+            ImportAction::Update(_, task, fields) => {
+                message.push(format!(
+                    "[{}]: {} ({})",
+                    update.blue(),
+                    task.reference(),
+                    fields.join(", ")
+                ));
+                summary.updated.push(task.reference());
+            }
+            ImportAction::Skip(task) => {
+                message.push(format!("[{}]: {}", skip.yellow(), task.reference()));
+                summary.skipped.push(task.reference());
+            }
+        }
+    }
+
+    message.push(format!(
+        "{} added, {} updated, {} skipped{}",
+        summary.added.len(),
+        summary.updated.len(),
+        summary.skipped.len(),
+        if is_dry_run {
+            ", nothing was changed"
+        } else {
+            ""
+        }
+    ));
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &summary,
+        &message.join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod test_import_app {
+    use super::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::storage::get_task_outputs;
+    use crate::common::testing::{self, in_time_zone};
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn imports_csv_written_by_export() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        testing::task()
+            .ticket("1234")
+            .description("Fix login, then logout")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Say \"hi\"\nthen leave")
+            .status(Status::Closed)
+            .insert(state.get_conn());
+        add_tags_to_task(1, &["backend".to_string()], state.get_conn()).unwrap();
+
+        let path = test_helpers::temp_path("roundtrip.csv");
+        let export = crate::subcommands::export::app().get_matches_from(vec![
+            "export",
+            "-o",
+            path.to_str().unwrap(),
+        ]);
+        assert!(crate::subcommands::export::handler(&export, &mut state).is_ok());
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let matches = app().get_matches_from(vec!["import", path.to_str().unwrap()]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        let first = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(first.ticket, Some("1234".to_string()));
+        assert_eq!(first.description, "Fix login, then logout");
+        assert_eq!(first.opened, 10000);
+        assert_eq!(
+            get_tags_for_task(1, state.get_conn()).unwrap(),
+            vec!["backend".to_string()]
+        );
+        let second = get_task_by_id(2, state.get_conn()).unwrap();
+        assert_eq!(second.ticket, None);
+        assert_eq!(second.description, "Say \"hi\"\nthen leave");
+        assert_eq!(second.status, Status::Closed as i16);
+        assert!(second.closed.is_some());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.ends_with("2 added, 0 updated, 0 skipped\n"));
+    }
+
+    #[test]
+    fn imports_json_and_creates_projects() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        let path = test_helpers::temp_path("tasks.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {"ticket": "API-1", "description": "Design the api", "project": "api", "priority": "p1", "due": "2020-10-02"},
+                {"description": "Wait on review", "status": "blocked", "blocked_reason": "Needs a reviewer"}
+            ]"#,
+        )
+        .unwrap();
+        let matches = app().get_matches_from(vec!["import", path.to_str().unwrap()]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        let first = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(first.opened, now.timestamp());
+        assert_eq!(first.priority, Some(Priority::P1 as i16));
+        assert_eq!(
+            first.due,
+            Some(Local.ymd(2020, 10, 2).and_hms(23, 59, 59).timestamp())
+        );
+        let project = get_project("api", state.get_conn()).unwrap();
+        assert_eq!(first.project_id, Some(project.id));
+
+        let second = get_task_by_id(2, state.get_conn()).unwrap();
+        assert_eq!(second.status, Status::Blocked as i16);
+        assert_eq!(second.blocked_reason, Some("Needs a reviewer".to_string()));
+    }
+
+    #[test]
+    fn imports_ndjson() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let path = test_helpers::temp_path("tasks.ndjson");
+        std::fs::write(
+            &path,
+            "{\"ticket\": \"1\", \"description\": \"First\"}\n\n{\"ticket\": \"2\", \"description\": \"Second\"}\n",
+        )
+        .unwrap();
+        let matches = app().get_matches_from(vec!["import", path.to_str().unwrap()]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        assert_eq!(test_helpers::task_count(state.get_conn()), 2);
+    }
+
+    #[test]
+    fn conflicts_fail_before_anything_is_imported() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        testing::task()
+            .ticket("1234")
+            .description("Existing")
+            .insert(state.get_conn());
+
+        let path = test_helpers::temp_path("conflict.csv");
+        std::fs::write(&path, "ticket,description\n999,New\n1234,Changed\n").unwrap();
+        let matches = app().get_matches_from(vec!["import", path.to_str().unwrap()]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(SuaideError::ImportConflictError(ticket)) => assert_eq!(ticket, "1234"),
+            _ => panic!("expected a conflict"),
+        }
+        assert_eq!(test_helpers::task_count(state.get_conn()), 1);
+    }
+
+    #[test]
+    fn conflicts_can_be_skipped() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        testing::task()
+            .ticket("1234")
+            .description("Existing")
+            .insert(state.get_conn());
+
+        let path = test_helpers::temp_path("skip.csv");
+        std::fs::write(&path, "ticket,description\n999,New\n1234,Changed\n").unwrap();
+        let matches = app().get_matches_from(vec![
+            "import",
+            path.to_str().unwrap(),
+            "--on-conflict",
+            "skip",
+        ]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        assert_eq!(test_helpers::task_count(state.get_conn()), 2);
+        let existing = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(existing.description, "Existing");
+    }
+
+    #[test]
+    fn conflicts_can_update_existing_tasks() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        testing::task()
+            .ticket("1234")
+            .description("Existing")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("5678")
+            .description("Same")
+            .insert(state.get_conn());
+
+        let path = test_helpers::temp_path("update.csv");
+        std::fs::write(
+            &path,
+            "ticket,description,status,tags\n1234,Changed,closed,review\n5678,Same,open,\n",
+        )
+        .unwrap();
+        let matches = app().get_matches_from(vec![
+            "import",
+            path.to_str().unwrap(),
+            "--on-conflict",
+            "update",
+        ]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        let updated = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(updated.description, "Changed");
+        assert_eq!(updated.status, Status::Closed as i16);
+        assert_eq!(
+            get_tags_for_task(1, state.get_conn()).unwrap(),
+            vec!["review".to_string()]
+        );
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("1234 (description, status, tags)"));
+        assert!(data.ends_with("0 added, 1 updated, 1 skipped\n"));
+    }
+
+    #[test]
+    fn dry_run_changes_nothing() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        testing::task()
+            .ticket("1234")
+            .description("Existing")
+            .insert(state.get_conn());
+
+        let path = test_helpers::temp_path("dry-run.csv");
+        std::fs::write(&path, "ticket,description\n999,New\n1234,Changed\n").unwrap();
+        let matches = app().get_matches_from(vec![
+            "import",
+            path.to_str().unwrap(),
+            "--on-conflict",
+            "update",
+            "--dry-run",
+        ]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        assert_eq!(test_helpers::task_count(state.get_conn()), 1);
+        let existing = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(existing.description, "Existing");
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.ends_with("1 added, 1 updated, 0 skipped, nothing was changed\n"));
+    }
+
+    #[test]
+    fn invalid_rows_are_reported() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let path = test_helpers::temp_path("invalid.csv");
+        std::fs::write(&path, "description,status\nFine,open\nBroken,finished\n").unwrap();
+        let matches = app().get_matches_from(vec!["import", path.to_str().unwrap()]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(SuaideError::ImportRowError(row, message)) => {
+                assert_eq!(row, 2);
+                assert_eq!(message, "unknown status finished");
+            }
+            _ => panic!("expected the row to be rejected"),
+        }
+        assert_eq!(test_helpers::task_count(state.get_conn()), 0);
+    }
+
+    #[test]
+    fn imports_taskwarrior_exports() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let path = test_helpers::temp_path("taskwarrior.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {"uuid": "8d0ab7a4-2b5c-4a0f-9f0a-6d1f0c9f3c11", "description": "Write the docs", "status": "completed", "entry": "20201001T090000Z", "end": "20201002T170000Z", "project": "docs", "tags": ["writing"], "priority": "H", "annotations": [{"entry": "20201001T100000Z", "description": "Outline done"}]},
+                {"uuid": "0f3e1c2d-5b6a-4c7d-8e9f-a0b1c2d3e4f5", "description": "Plan the release", "status": "waiting", "entry": "20201001T090000Z", "due": "20201009T000000Z"}
+            ]"#,
+        )
+        .unwrap();
+        let matches =
+            app().get_matches_from(vec!["import", path.to_str().unwrap(), "-f", "taskwarrior"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let first = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(
+            first.uuid,
+            Some("8d0ab7a4-2b5c-4a0f-9f0a-6d1f0c9f3c11".to_string())
+        );
+        assert_eq!(first.status, Status::Closed as i16);
+        assert_eq!(
+            first.opened,
+            Utc.ymd(2020, 10, 1).and_hms(9, 0, 0).timestamp()
+        );
+        assert_eq!(
+            first.closed,
+            Some(Utc.ymd(2020, 10, 2).and_hms(17, 0, 0).timestamp())
+        );
+        assert_eq!(first.priority, Some(Priority::P1 as i16));
+        let notes = get_notes_for_task(1, state.get_conn()).unwrap();
+        assert_eq!(notes[0].body, "Outline done");
+        assert_eq!(
+            notes[0].created,
+            Utc.ymd(2020, 10, 1).and_hms(10, 0, 0).timestamp()
+        );
+
+        let second = get_task_by_id(2, state.get_conn()).unwrap();
+        assert_eq!(second.status, Status::Open as i16);
+        assert_eq!(
+            second.due,
+            Some(Utc.ymd(2020, 10, 9).and_hms(0, 0, 0).timestamp())
+        );
+
+        // Importing the same export again matches the tasks by their uuid
+        let matches = app().get_matches_from(vec![
+            "import",
+            path.to_str().unwrap(),
+            "-f",
+            "taskwarrior",
+            "--on-conflict",
+            "skip",
+        ]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(test_helpers::task_count(state.get_conn()), 2);
+    }
+
+    #[test]
+    fn round_trips_taskwarrior_exports() {
+        use crate::schema::suaide::dsl::{id, suaide};
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        testing::task()
+            .ticket("1234")
+            .description("Keep me")
+            .priority(Priority::P0)
+            .closed(30000)
+            .insert(state.get_conn());
+        testing::task()
+            .description("In flight")
+            .status(Status::InProgress)
+            .priority(Priority::P3)
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("5678")
+            .description("Waiting on review")
+            .blocked("Needs a reviewer")
+            .insert(state.get_conn());
+        add_tags_to_task(1, &["backend".to_string()], state.get_conn()).unwrap();
+        add_note_to_task(1, "A note".to_string(), 20000, state.get_conn()).unwrap();
+
+        let path = test_helpers::temp_path("round-trip.json");
+        let export = crate::subcommands::export::app().get_matches_from(vec![
+            "export",
+            "-f",
+            "taskwarrior",
+            "-o",
+            path.to_str().unwrap(),
+        ]);
+        assert!(crate::subcommands::export::handler(&export, &mut state).is_ok());
+        let exported = suaide
+            .order_by(id.asc())
+            .load::<Task>(state.get_conn())
+            .unwrap();
+        let exported_outputs = get_task_outputs(&exported, state.get_conn()).unwrap();
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let matches =
+            app().get_matches_from(vec!["import", path.to_str().unwrap(), "-f", "taskwarrior"]);
+        let result = handler(&matches, &mut state);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        let imported = suaide
+            .order_by(id.asc())
+            .load::<Task>(state.get_conn())
+            .unwrap();
+        assert_eq!(imported, exported);
+        let imported_outputs = get_task_outputs(&imported, state.get_conn()).unwrap();
+        assert_eq!(
+            serde_json::to_value(imported_outputs).unwrap(),
+            serde_json::to_value(exported_outputs).unwrap()
+        );
+    }
+
+    #[test]
+    fn dates_that_start_after_midnight() {
+        if !in_time_zone(
+            module_path!(),
+            "dates_that_start_after_midnight",
+            "America/Sao_Paulo",
+        ) {
+            return;
+        }
+        // Brazil's clocks went forward at midnight on the 4th of November 2018
+        assert_eq!(
+            parse_timestamp("2018-11-04", false),
+            Some(Local.ymd(2018, 11, 4).and_hms(1, 0, 0).timestamp())
+        );
+        assert_eq!(
+            parse_timestamp("4 Nov 2018", true),
+            Some(Local.ymd(2018, 11, 4).and_hms(23, 59, 59).timestamp())
+        );
+    }
+
+    #[test]
+    fn parses_quoted_csv_fields() {
+        let records = parse_csv("a,b\r\n\"x, y\",\"say \"\"hi\"\"\nbye\"\n").unwrap();
+        assert_eq!(
+            records,
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["x, y".to_string(), "say \"hi\"\nbye".to_string()],
+            ]
+        );
+        assert!(parse_csv("a\n\"unterminated\n").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_helpers {
+    use diesel::prelude::*;
+
+    use std::path::PathBuf;
+
+    pub fn task_count(db_conn: &SqliteConnection) -> i64 {
+        use crate::schema::suaide::dsl::suaide;
+
+        suaide
+            .count()
+            .get_result(db_conn)
+            .expect("Count should be successful")
+    }
+
+    pub fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("suaide-import-{}-{}", std::process::id(), name))
+    }
+}
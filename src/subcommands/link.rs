@@ -0,0 +1,129 @@
+use clap::{App, Arg, ArgMatches};
+use colored::Colorize;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{add_dependency, get_task_output};
+use crate::domain::{SuaideError, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("link")
+        .about("Mark a task as blocking another")
+        .arg(
+            Arg::with_name("task")
+                .index(1)
+                .required(true)
+                .help("The task that has to be done first")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("blocks")
+                .long("blocks")
+                .short("b")
+                .required(true)
+                .help("The task that can't be done until the first one is")
+                .takes_value(true),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    if let (Some(task), Some(blocks)) = (matches.value_of("task"), matches.value_of("blocks")) {
+        let task = state.find_task(task)?;
+        let blocked = state.find_task(blocks)?;
+        add_dependency(blocked.id, task.id, state.get_conn())?;
+
+        let output = get_task_output(&blocked, state.get_conn())?;
+        let message = format!(
+            "{} {} {}",
+            TaskOutput::reference(&task),
+            "now blocks".red(),
+            TaskOutput::reference(&blocked)
+        );
+        return TaskRenderer::new(state.get_output_format(), false).render_message(
+            state.writer(),
+            &output,
+            &message,
+        );
+    }
+    Err(SuaideError::IncorrectArgs)
+}
+
+#[cfg(test)]
+mod test_link_app {
+    use super::*;
+
+    use crate::common::storage::get_dependencies;
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn links_tasks() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["link", "1234", "--blocks", "2"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let dependencies = get_dependencies(2, state.get_conn()).unwrap();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].id, 1);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("1234"));
+        assert!(data.contains("#2"));
+    }
+
+    #[test]
+    fn refuses_cycles() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["link", "1", "--blocks", "2"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let matches = app().get_matches_from(vec!["link", "2", "--blocks", "3"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["link", "3", "--blocks", "1"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::DependencyCycleError)
+        ));
+        let matches = app().get_matches_from(vec!["link", "1", "--blocks", "1"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::DependencyCycleError)
+        ));
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["link", "1234", "-b", "2"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["id"], 2);
+        assert_eq!(output["blocked_by"], serde_json::json!(["1234"]));
+    }
+}
@@ -1,11 +1,17 @@
-use chrono::prelude::*;
 use clap::{App, Arg, ArgMatches};
 use std::io;
 
 use diesel::prelude::*;
 
-use crate::common::time::{calculate_duration_from_dates, calculate_duration_from_timeframe};
-use crate::domain::{SuaideError, Task};
+use crate::common::args::{
+    duration_arg, tags_arg, values_to_vec, window_from_matches, STATUS_FILTERS, TIMEFRAMES,
+};
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    apply_deleted_filter, apply_project_filter, apply_ready_filter, apply_status_filter,
+    apply_tag_filters, get_task_outputs, window_query,
+};
+use crate::domain::{SortBy, Status, SuaideError, Task};
 use crate::state::State;
 
 pub fn app<'a>() -> App<'a, 'static> {
@@ -15,39 +21,64 @@ pub fn app<'a>() -> App<'a, 'static> {
             Arg::with_name("timeframe")
                 .index(1)
                 .help("What timeframe would you like to list the tasks for?")
+                .long_help(
+                    "What timeframe would you like to list the tasks for?\n\"overdue\" lists unfinished tasks that are past their due date, \"upcoming\" lists unfinished tasks due within the next week\n",
+                )
                 .conflicts_with("duration")
                 .default_value("today")
-                .possible_values(&["today", "yesterday", "week", "lastweek", "month", "all"])
+                .possible_values(&TIMEFRAMES)
                 .takes_value(true),
         )
+        .arg(duration_arg())
         .arg(
-            Arg::with_name("duration")
-                .long("duration")
-                .short("d")
-                .conflicts_with("timeframe")
-                .number_of_values(2)
-                .next_line_help(true)
+            Arg::with_name("status")
+                .long("status")
+                .short("s")
+                .help("Only list tasks with this status, can be repeated or comma separated")
                 .long_help(
-                    "Search for all tasks between two dates. \nDates should be provided in one of the following formats \"YYYY-MM-DD\" or \"DD mmm YYYY\"\nExample: 2020-01-01 or 1 Jan 2020\n",
+                    "Only list tasks with this status, can be repeated or comma separated.\n\"active\" covers open, in-progress and blocked tasks, \"done\" covers closed and cancelled tasks\n",
                 )
+                .multiple(true)
+                .number_of_values(1)
+                .use_delimiter(true)
+                .possible_values(&STATUS_FILTERS)
                 .takes_value(true),
         )
-        // @todo
-        // .arg(
-        //     Arg::with_name("status")
-        //         .long("status")
-        //         .short('s')
-        //         .about("Specify a status of the tasks you would like to list")
-        //         .default_value("all")
-        //         .possible_values(&["open", "closed", "all"])
-        //         .takes_value(true),
-        // )
         .arg(
             Arg::with_name("verbose")
                 .long("verbose")
                 .short("v")
                 .help("Provide additional information about each task"),
         )
+        .arg(tags_arg(
+            "tag",
+            "Only include tasks with this label, can be repeated or comma separated",
+        ))
+        .arg(
+            tags_arg(
+                "exclude_tag",
+                "Exclude tasks with this label, can be repeated or comma separated",
+            )
+            .long("exclude-tag"),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .long("sort")
+                .help("How to order the tasks, defaults to status then priority")
+                .possible_values(&["status", "priority", "opened", "closed", "due", "ticket"])
+                .default_value("status")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ready")
+                .long("ready")
+                .help("Only list unfinished tasks that aren't waiting on any other task"),
+        )
+        .arg(
+            Arg::with_name("all_projects")
+                .long("all-projects")
+                .help("Include tasks from every project, not just the active one"),
+        )
 }
 
 pub fn handler<W: io::Write>(
@@ -55,33 +86,485 @@ pub fn handler<W: io::Write>(
     state: &mut State<W>,
 ) -> Result<(), SuaideError> {
     let is_verbose = matches.is_present("verbose");
-    let (mut start, mut end): (i64, i64) = (0, Local::now().timestamp());
-    if let Some(duration_iter) = matches.values_of("duration") {
-        let duration: Vec<&str> = duration_iter.collect();
-        if duration.len() != 2 {
-            return Err(SuaideError::IncorrectArgs);
-        }
-        let result = calculate_duration_from_dates(duration[0], duration[1])?;
-        start = result.0;
-        end = result.1;
+    let included_tags = values_to_vec(matches, "tag");
+    let excluded_tags = values_to_vec(matches, "exclude_tag");
+    let mut statuses: Vec<Status> = values_to_vec(matches, "status")
+        .iter()
+        .flat_map(|s| Status::from_filter(s))
+        .collect();
+    statuses.sort();
+    statuses.dedup();
+    let project = if matches.is_present("all_projects") {
+        None
     } else {
-        let tf = matches.value_of("timeframe").expect("has default value");
-        if tf != "all" {
-            let result = calculate_duration_from_timeframe(Local::now().date(), tf.into());
-            start = result.0;
-            end = result.1;
-        }
+        state.get_project().map(|p| p.id)
     };
+    let now = state.now();
+    let (start, end, by_due_date) = window_from_matches(matches, now)?;
+    if by_due_date && statuses.is_empty() {
+        statuses = Status::from_filter("active");
+    }
 
-    use crate::schema::suaide::dsl::{closed, opened, suaide};
+    use crate::schema::suaide::dsl::closed;
 
-    let mut results = suaide
-        .filter(opened.between(start, end))
-        .or_filter(closed.between(start, end))
+    let query = apply_deleted_filter(window_query(start, end, by_due_date));
+    let query = if matches.is_present("ready") {
+        if statuses.is_empty() {
+            statuses = Status::from_filter("active");
+        }
+        apply_ready_filter(query, state.get_conn())?
+    } else {
+        query
+    };
+    let query = apply_project_filter(query, project);
+    let query = apply_status_filter(query, &statuses);
+    let mut results = apply_tag_filters(query, &included_tags, &excluded_tags)
         .order_by(closed.asc())
         .load::<Task>(state.get_conn())?;
 
-    results.sort();
-    results.iter().for_each(|result| result.print(is_verbose));
-    Ok(())
+    SortBy::from(matches.value_of("sort").expect("has default value")).sort(&mut results);
+    let output = get_task_outputs(&results, state.get_conn())?;
+    TaskRenderer::new(state.get_output_format(), is_verbose)
+        .with_now(now)
+        .render_tasks(state.writer(), &output)
+}
+
+#[cfg(test)]
+mod test_list_app {
+    use super::*;
+
+    use crate::common::storage::add_tags_to_task;
+    use crate::common::testing;
+    use crate::domain::{OutputFormat, Priority};
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn lists_all_tasks() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["list", "all"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("Open"));
+        assert!(lines[0].contains("1234: Open Task"));
+        assert!(lines[1].contains("In Progress"));
+        assert!(lines[1].contains(": In Progress Task"));
+    }
+
+    #[test]
+    fn filters_by_status() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Closed Task")
+            .status(Status::Closed)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Cancelled Task")
+            .status(Status::Cancelled)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["list", "all", "--status", "done"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(!data.contains("Open Task"));
+        assert!(data.contains("Closed Task"));
+        assert!(data.contains("Cancelled Task"));
+    }
+
+    #[test]
+    fn filters_by_blocked_status() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Blocked Task")
+            .status(Status::Blocked)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from_safe(vec!["list", "all", "--status", "blocked"]);
+        let result = handler(&matches.expect("blocked is a status"), &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(!data.contains("Open Task"));
+        assert!(data.contains("Blocked Task"));
+    }
+
+    #[test]
+    fn filters_by_tag() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        add_tags_to_task(2, &["backend".to_string()], state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["list", "all", "--tag", "backend", "-v"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(!data.contains("Open Task"));
+        assert!(data.contains("In Progress Task"));
+        assert!(data.contains("Opened: 1970-01-01"));
+        assert!(data.contains("Tags:"));
+        assert!(data.contains("backend"));
+    }
+
+    #[test]
+    fn verbose_output_includes_notes() {
+        use crate::common::storage::add_note_to_task;
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("Open Task")
+            .insert(state.get_conn());
+        add_note_to_task(1, "First note".to_string(), 10000, state.get_conn()).unwrap();
+        add_note_to_task(1, "Second note".to_string(), 20000, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["list", "all"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let matches = app().get_matches_from(vec!["list", "all", "-v"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let (plain, verbose) = data.split_at(data.find("Notes:").expect("should have notes"));
+        assert!(!plain.contains("First note"));
+        let first = verbose
+            .find("First note")
+            .expect("should have the first note");
+        let second = verbose
+            .find("Second note")
+            .expect("should have the second note");
+        assert!(first < second);
+    }
+
+    #[test]
+    fn nests_subtasks_under_their_parent() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1234")
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .description("First Step")
+            .status(Status::Closed)
+            .parent(1)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Second Step")
+            .parent(1)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["list", "all"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("Open Task"));
+        assert!(lines[1].contains("1234: In Progress Task (1/2 done)"));
+        assert!(lines[2].starts_with("└─ ") && lines[2].contains("Second Step"));
+        assert!(lines[3].starts_with("└─ ") && lines[3].contains("First Step"));
+    }
+
+    #[test]
+    fn lists_ready_tasks() {
+        use crate::common::storage::add_dependency;
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("1")
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("2")
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("3")
+            .description("Closed Task")
+            .status(Status::Closed)
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("4")
+            .description("Cancelled Task")
+            .status(Status::Cancelled)
+            .insert(state.get_conn());
+        // 1 is waiting on an unfinished task, 2 only on finished ones
+        add_dependency(1, 2, state.get_conn()).unwrap();
+        add_dependency(2, 3, state.get_conn()).unwrap();
+        add_dependency(2, 4, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["list", "all", "--ready"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("2: In Progress Task"));
+    }
+
+    #[test]
+    fn verbose_output_includes_blockers() {
+        use crate::common::storage::add_dependency;
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("T-1")
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("T-2")
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("T-3")
+            .description("Closed Task")
+            .status(Status::Closed)
+            .insert(state.get_conn());
+        add_dependency(1, 2, state.get_conn()).unwrap();
+        add_dependency(1, 3, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["list", "all", "-v"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let blocked_by: Vec<&str> = data.lines().filter(|l| l.contains("Blocked by:")).collect();
+        assert_eq!(blocked_by.len(), 1);
+        assert!(blocked_by[0].contains("T-2"));
+        assert!(!blocked_by[0].contains("T-3"));
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        testing::task()
+            .ticket("1234")
+            .description("Open Task")
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["list", "all"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output[0]["ticket"], "1234");
+        assert_eq!(output[0]["status"], "open");
+    }
+
+    fn state_with_due_tasks<W: std::io::Write>(writer: W) -> State<W> {
+        use crate::common::clock::FixedClock;
+        use chrono::prelude::*;
+
+        let mut state = State::new(writer).unwrap();
+        state.set_clock(Box::new(FixedClock::new(
+            Local.ymd(2020, 10, 7).and_hms(9, 0, 0),
+        )));
+
+        let due = |day| Local.ymd(2020, 10, day).and_hms(23, 59, 59).timestamp();
+        testing::task()
+            .description("Overdue Task")
+            .due(due(6))
+            .insert(state.get_conn());
+        testing::task()
+            .description("Closed Late")
+            .status(Status::Closed)
+            .due(due(5))
+            .insert(state.get_conn());
+        testing::task()
+            .description("Due Friday")
+            .status(Status::InProgress)
+            .due(due(9))
+            .insert(state.get_conn());
+        testing::task()
+            .description("Due Later")
+            .due(due(30))
+            .insert(state.get_conn());
+        state
+    }
+
+    #[test]
+    fn lists_overdue_tasks() {
+        let mut writer = Vec::new();
+        let mut state = state_with_due_tasks(&mut writer);
+
+        let matches = app().get_matches_from(vec!["list", "overdue"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("Overdue Task"));
+        assert!(lines[0].contains("(overdue)"));
+    }
+
+    #[test]
+    fn sorts_by_priority_across_statuses() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("Low Open")
+            .priority(Priority::P3)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Unprioritised In Progress")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Critical Blocked")
+            .blocked("Waiting")
+            .priority(Priority::P0)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["list", "all", "--sort", "priority"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let critical = data.find("Critical Blocked").expect("should be listed");
+        let low = data.find("Low Open").expect("should be listed");
+        let unprioritised = data
+            .find("Unprioritised In Progress")
+            .expect("should be listed");
+        assert!(critical < low);
+        assert!(low < unprioritised);
+    }
+
+    #[test]
+    fn sorts_by_priority_within_status() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("No Priority")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Low")
+            .priority(Priority::P3)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Critical")
+            .priority(Priority::P0)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["list", "all"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert!(lines[0].contains("P0") && lines[0].contains("Critical"));
+        assert!(lines[1].contains("P3") && lines[1].contains("Low"));
+        assert!(lines[2].contains("No Priority"));
+    }
+
+    #[test]
+    fn sorts_by_due_date() {
+        let mut writer = Vec::new();
+        let mut state = state_with_due_tasks(&mut writer);
+
+        let matches = app().get_matches_from(vec!["list", "all", "--sort", "due"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert!(lines[0].contains("Closed Late"));
+        assert!(lines[1].contains("Overdue Task"));
+        assert!(lines[2].contains("Due Friday"));
+        assert!(lines[3].contains("Due Later"));
+    }
+
+    #[test]
+    fn sorts_by_ticket() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("B-2")
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("A-1")
+            .description("Closed Task")
+            .status(Status::Closed)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["list", "all", "--sort", "ticket"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let lines: Vec<&str> = data.lines().collect();
+        assert!(lines[0].contains("A-1"));
+        assert!(lines[1].contains("B-2"));
+        assert!(lines[2].contains("In Progress Task"));
+    }
+
+    #[test]
+    fn lists_upcoming_tasks() {
+        let mut writer = Vec::new();
+        let mut state = state_with_due_tasks(&mut writer);
+
+        let matches = app().get_matches_from(vec!["list", "upcoming", "-v"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Due Friday"));
+        assert!(data.contains("Due: 2020-10-09 23:59"));
+        assert!(!data.contains("Overdue Task"));
+        assert!(!data.contains("Due Later"));
+        assert!(!data.contains("(overdue)"));
+    }
 }
@@ -1,7 +1,22 @@
 pub mod add;
 pub mod close;
 pub mod edit;
+pub mod export;
+pub mod history;
+pub mod import;
+pub mod link;
 pub mod list;
+pub mod note;
+pub mod project;
+pub mod recur;
+pub mod redo;
 pub mod remove;
+pub mod report;
+pub mod search;
 pub mod stand_up;
+pub mod start;
 pub mod status;
+pub mod stop;
+pub mod trash;
+pub mod undo;
+pub mod unlink;
@@ -0,0 +1,141 @@
+use clap::{App, Arg, ArgMatches};
+use colored::Colorize;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{add_note_to_task, get_task, get_task_output};
+use crate::domain::SuaideError;
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("note")
+        .about("Add a note to a task")
+        .arg(
+            Arg::with_name("task")
+                .index(1)
+                .required(true)
+                .help("The task to add the note to")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("body")
+                .index(2)
+                .help("The note, you'll be prompted for it if it isn't provided")
+                .takes_value(true),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    if let Some(task_id) = matches.value_of("task") {
+        let task_id = state.generate_ticket_id(Some(task_id)).unwrap();
+        let task = get_task(&task_id, state.get_conn())?;
+        let body = match matches.value_of("body") {
+            Some(body) => body.to_string(),
+            None => state.get_input("note", None)?,
+        };
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(SuaideError::IncorrectArgs);
+        }
+
+        add_note_to_task(
+            task.id,
+            body.to_string(),
+            state.now().timestamp(),
+            state.get_conn(),
+        )?;
+
+        let output = get_task_output(&task, state.get_conn())?;
+        let message = format!("{}: {}", "Added note".green(), body);
+        return TaskRenderer::new(state.get_output_format(), false).render_message(
+            state.writer(),
+            &output,
+            &message,
+        );
+    }
+    Err(SuaideError::IncorrectArgs)
+}
+
+#[cfg(test)]
+mod test_note_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::storage::get_notes_for_task;
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn adds_a_note() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 7).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["note", "1234", "Spoke to the API team"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let notes = get_notes_for_task(1, state.get_conn()).expect("This should return an Ok");
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].body, "Spoke to the API team");
+        assert_eq!(notes[0].created, now.timestamp());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Spoke to the API team"));
+    }
+
+    #[test]
+    fn prompts_for_the_note() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        state.set_prompter(Box::new(ScriptedPrompter::default().input("Prompted note")));
+
+        let matches = app().get_matches_from(vec!["note", "1"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let notes = get_notes_for_task(1, state.get_conn()).expect("This should return an Ok");
+        assert_eq!(notes[0].body, "Prompted note");
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["note", "1234", "First"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["notes"][0]["body"], "First");
+        assert!(output["notes"][0]["created"].is_string());
+    }
+
+    #[test]
+    fn should_error_with_not_found() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["note", "1234", "Note"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use colored::Colorize;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{add_project, get_project, get_projects, set_active_project};
+use crate::domain::{AddProject, SuaideError};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("project")
+        .about("Manage the projects tasks are grouped into")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("add")
+                .about("Add a new project")
+                .arg(
+                    Arg::with_name("name")
+                        .index(1)
+                        .required(true)
+                        .help("The name of the project")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("prefix")
+                        .long("prefix")
+                        .short("p")
+                        .help("The ticket prefix used for tasks within this project")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("switch")
+                        .long("switch")
+                        .short("s")
+                        .help("Make the new project the active project"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("list").about("List all projects"))
+        .subcommand(
+            SubCommand::with_name("switch")
+                .about("Change the active project")
+                .arg(
+                    Arg::with_name("name")
+                        .index(1)
+                        .required_unless("none")
+                        .help("The project to switch to")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("none")
+                        .long("none")
+                        .conflicts_with("name")
+                        .help("Clear the active project"),
+                ),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    match matches.subcommand() {
+        ("add", Some(matches)) => add(matches, state),
+        ("list", Some(_)) => list(state),
+        ("switch", Some(matches)) => switch(matches, state),
+        _ => Err(SuaideError::SubCommandNotFound),
+    }
+}
+
+fn add<W: io::Write>(matches: &ArgMatches, state: &mut State<W>) -> Result<(), SuaideError> {
+    let name = matches.value_of("name").ok_or(SuaideError::IncorrectArgs)?;
+    let project = AddProject {
+        name: name.to_string(),
+        prefix: matches.value_of("prefix").unwrap_or_default().to_string(),
+    };
+    let mut project = add_project(&project, state.get_conn())?;
+    if matches.is_present("switch") {
+        set_active_project(Some(project.id), state.get_conn())?;
+        project.active = true;
+    }
+
+    let mut message = format!("{}: {}", "Added project".green(), project.name);
+    if project.active {
+        message += &format!("\n{}: {}", "Switched to project".green(), project.name);
+    }
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &project,
+        &message,
+    )?;
+
+    if project.active {
+        state.set_project(Some(project));
+    }
+    Ok(())
+}
+
+fn list<W: io::Write>(state: &mut State<W>) -> Result<(), SuaideError> {
+    let projects = get_projects(state.get_conn())?;
+    let message: Vec<String> = projects.iter().map(ToString::to_string).collect();
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &projects,
+        &message.join("\n"),
+    )
+}
+
+fn switch<W: io::Write>(matches: &ArgMatches, state: &mut State<W>) -> Result<(), SuaideError> {
+    if matches.is_present("none") {
+        set_active_project(None, state.get_conn())?;
+        TaskRenderer::new(state.get_output_format(), false).render_message(
+            state.writer(),
+            &serde_json::Value::Null,
+            &"Cleared active project".green().to_string(),
+        )?;
+        state.set_project(None);
+        return Ok(());
+    }
+
+    let name = matches.value_of("name").ok_or(SuaideError::IncorrectArgs)?;
+    let mut project = get_project(name, state.get_conn())?;
+    set_active_project(Some(project.id), state.get_conn())?;
+    project.active = true;
+    let message = format!("{}: {}", "Switched to project".green(), project.name);
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &project,
+        &message,
+    )?;
+    state.set_project(Some(project));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_project_app {
+    use super::*;
+
+    use crate::common::storage::get_active_project;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn add_and_switch_project() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["project", "add", "backend", "-p", "BE-"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(get_active_project(state.get_conn()).unwrap().is_none());
+
+        let matches = app().get_matches_from(vec!["project", "switch", "backend"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let active = get_active_project(state.get_conn())
+            .unwrap()
+            .expect("project should be active");
+        assert_eq!(active.name, "backend".to_string());
+        assert_eq!(state.get_project(), Some(&active));
+        assert_eq!(state.get_ticket_prefix(), "BE-");
+        assert_eq!(
+            state.generate_ticket_id(Some("12")),
+            Some("BE-12".to_string())
+        );
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Switched to project"));
+    }
+
+    #[test]
+    fn only_one_project_is_active() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        for name in &["backend", "frontend"] {
+            let matches = app().get_matches_from(vec!["project", "add", name, "--switch"]);
+            assert!(handler(&matches, &mut state).is_ok());
+        }
+
+        let active: Vec<_> = get_projects(state.get_conn())
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.active)
+            .collect();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].name, "frontend".to_string());
+
+        let matches = app().get_matches_from(vec!["project", "switch", "--none"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(get_active_project(state.get_conn()).unwrap().is_none());
+        assert!(state.get_project().is_none());
+    }
+
+    #[test]
+    fn should_error_on_duplicate_project() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["project", "add", "backend"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        match handler(&matches, &mut state).unwrap_err() {
+            SuaideError::ProjectAlreadyExistsError => {}
+            _ => panic!("Expected project already exists error"),
+        }
+    }
+
+    #[test]
+    fn should_error_switching_to_unknown_project() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["project", "switch", "backend"]);
+        match handler(&matches, &mut state).unwrap_err() {
+            SuaideError::ProjectNotFound => {}
+            _ => panic!("Expected project not found error"),
+        }
+    }
+}
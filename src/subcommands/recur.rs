@@ -0,0 +1,270 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use colored::Colorize;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{get_recurrences, get_task_by_id, get_task_output, remove_recurrence};
+use crate::domain::{RecurrenceOutput, SuaideError, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("recur")
+        .about("Manage repeating tasks")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("list").about("List the repeating tasks"))
+        .subcommand(
+            SubCommand::with_name("remove")
+                .about("Stop a task from repeating, any tasks already added are kept")
+                .arg(
+                    Arg::with_name("task")
+                        .index(1)
+                        .required(true)
+                        .help("The task that was added with --recur")
+                        .takes_value(true),
+                ),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    match matches.subcommand() {
+        ("list", Some(_)) => list(state),
+        ("remove", Some(matches)) => remove(matches, state),
+        _ => Err(SuaideError::SubCommandNotFound),
+    }
+}
+
+fn list<W: io::Write>(state: &mut State<W>) -> Result<(), SuaideError> {
+    let mut output = Vec::new();
+    for recurrence in get_recurrences(state.get_conn())? {
+        // Templates that have been purged from the trash no longer repeat
+        if let Ok(template) = get_task_by_id(recurrence.task_id, state.get_conn()) {
+            output.push(RecurrenceOutput::new(&recurrence, &template));
+        }
+    }
+
+    let message: Vec<String> = output
+        .iter()
+        .map(|recurrence| {
+            format!(
+                "{}: {} ({}, next due {})",
+                recurrence.task.bold(),
+                recurrence.description,
+                recurrence.rule.italic(),
+                recurrence.next_due.format("%a %Y-%m-%d")
+            )
+        })
+        .collect();
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &output,
+        &message.join("\n"),
+    )
+}
+
+fn remove<W: io::Write>(matches: &ArgMatches, state: &mut State<W>) -> Result<(), SuaideError> {
+    let task = matches.value_of("task").ok_or(SuaideError::IncorrectArgs)?;
+    let template = state.find_task(task)?;
+    remove_recurrence(template.id, state.get_conn())?;
+
+    let output = get_task_output(&template, state.get_conn())?;
+    let message = format!(
+        "[{}]: Task {} no longer repeats",
+        "Stopped".yellow(),
+        TaskOutput::reference(&template)
+    );
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &output,
+        &message,
+    )
+}
+
+#[cfg(test)]
+mod test_recur_app {
+    use super::*;
+
+    use chrono::prelude::*;
+    use diesel::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::storage::{get_tags_for_task, materialise_recurrences};
+    use crate::domain::{RecurrenceRule, Status, Task};
+    use crate::schema::suaide::dsl::{status, suaide};
+    use crate::state::State;
+    use crate::subcommands::{add, close, undo};
+
+    use std::str::from_utf8;
+
+    fn due_on(task: &Task) -> Date<Local> {
+        Local.timestamp(task.due.unwrap(), 0).date()
+    }
+
+    #[test]
+    fn adds_a_repeating_task_due_on_its_first_day() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        // A Thursday
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        let matches = add::app().get_matches_from(vec![
+            "add",
+            "-d",
+            "Dependency review",
+            "--recur",
+            "weekly:mon",
+        ]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+
+        let task: Task = suaide.find(1).first(state.get_conn()).unwrap();
+        assert_eq!(due_on(&task), Local.ymd(2020, 10, 5));
+
+        let matches = app().get_matches_from(vec!["recur", "list"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("(repeats weekly:mon)"));
+        assert!(data.contains("Dependency review"));
+        assert!(data.contains("next due Mon 2020-10-12"));
+    }
+
+    #[test]
+    fn adds_the_next_instance_once_the_last_is_closed() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        let matches = add::app().get_matches_from(vec![
+            "add",
+            "-t",
+            "OPS-1",
+            "-d",
+            "On-call handover",
+            "--recur",
+            "weekdays",
+            "--tag",
+            "oncall",
+        ]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let created = materialise_recurrences(now.date(), now.timestamp(), state.get_conn());
+        assert_eq!(created.unwrap().len(), 0);
+
+        let matches = close::app().get_matches_from(vec!["close", "OPS-1"]);
+        assert!(close::handler(&matches, &mut state).is_ok());
+        let created = materialise_recurrences(now.date(), now.timestamp(), state.get_conn())
+            .expect("This should return an Ok");
+
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].description, "On-call handover");
+        assert_eq!(created[0].ticket, None);
+        assert_eq!(created[0].status, Status::Open as i16);
+        // Friday follows the Thursday it was first due
+        assert_eq!(due_on(&created[0]), Local.ymd(2020, 10, 2));
+        let tags = get_tags_for_task(created[0].id, state.get_conn()).unwrap();
+        assert_eq!(tags, vec!["oncall".to_string()]);
+    }
+
+    #[test]
+    fn adds_the_latest_instance_once_it_comes_around() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        let matches =
+            add::app().get_matches_from(vec!["add", "-d", "Pay invoices", "--recur", "monthly:31"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let task: Task = suaide.find(1).first(state.get_conn()).unwrap();
+        assert_eq!(due_on(&task), Local.ymd(2020, 10, 31));
+
+        // The months in between were missed, so only March's is added
+        let later = Local.ymd(2021, 3, 2).and_hms(9, 0, 0);
+        let created = materialise_recurrences(later.date(), later.timestamp(), state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(created.len(), 1);
+        assert_eq!(due_on(&created[0]), Local.ymd(2021, 2, 28));
+
+        let open: i64 = suaide
+            .filter(status.eq(Status::Open as i16))
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(open, 2);
+    }
+
+    #[test]
+    fn added_instances_can_be_undone() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        let matches = add::app().get_matches_from(vec![
+            "add", "-t", "OPS-1", "-d", "Standup", "--recur", "daily",
+        ]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let later = now + chrono::Duration::days(1);
+        let created = materialise_recurrences(later.date(), later.timestamp(), state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(created.len(), 1);
+
+        let matches = undo::app().get_matches_from(vec!["undo"]);
+        assert!(undo::handler(&matches, &mut state).is_ok());
+        let remaining: i64 = suaide.count().get_result(state.get_conn()).unwrap();
+        assert_eq!(remaining, 1);
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("recur "));
+    }
+
+    #[test]
+    fn stops_a_task_repeating() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = add::app().get_matches_from(vec![
+            "add", "-t", "OPS-1", "-d", "Standup", "--recur", "daily",
+        ]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["recur", "remove", "OPS-1"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::RecurrenceNotFound)
+        ));
+
+        let later = state.now() + chrono::Duration::days(3);
+        let created = materialise_recurrences(later.date(), later.timestamp(), state.get_conn());
+        assert_eq!(created.unwrap().len(), 0);
+    }
+
+    #[test]
+    fn parses_recurrence_rules() {
+        for rule in &["daily", "weekdays", "weekly:mon,thu", "monthly:15"] {
+            let parsed: RecurrenceRule = rule.parse().expect("should be a valid rule");
+            assert_eq!(parsed.to_string(), *rule);
+        }
+        let parsed: RecurrenceRule = "Weekly:Friday,mon".parse().unwrap();
+        assert_eq!(
+            parsed,
+            RecurrenceRule::Weekly(vec![Weekday::Mon, Weekday::Fri])
+        );
+
+        for rule in &[
+            "hourly",
+            "weekly",
+            "weekly:someday",
+            "monthly:32",
+            "daily:1",
+        ] {
+            assert!(matches!(
+                rule.parse::<RecurrenceRule>(),
+                Err(SuaideError::RecurrenceFormatError(_))
+            ));
+        }
+    }
+}
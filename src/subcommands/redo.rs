@@ -0,0 +1,105 @@
+use clap::{App, ArgMatches};
+use colored::Colorize;
+use serde_json::json;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{apply_journal_entry, get_journal_entry, get_task_outputs};
+use crate::domain::{SuaideError, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("redo")
+        .about("Redo the last command that was undone")
+        .after_help(
+            "A task's own fields and any tags edit changed are reapplied, its notes and \
+             dependencies are left as they are",
+        )
+}
+
+pub fn handler<W: io::Write>(
+    _matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let entry =
+        get_journal_entry(true, state.get_conn())?.ok_or(SuaideError::NothingToRedoError)?;
+    let tasks = apply_journal_entry(&entry, false, state.get_conn())?;
+
+    let references: Vec<String> = tasks.iter().map(TaskOutput::reference).collect();
+    let message = format!(
+        "[{}]: {} {}",
+        "Redone".yellow(),
+        entry.command,
+        references.join(", ")
+    );
+    let tasks = get_task_outputs(&tasks, state.get_conn())?;
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &json!({ "command": entry.command, "tasks": tasks }),
+        &message,
+    )
+}
+
+#[cfg(test)]
+mod test_redo_app {
+    use super::*;
+
+    use crate::common::storage::get_task_by_id;
+    use crate::domain::Status;
+    use crate::state::State;
+    use crate::subcommands::{add, close, status, undo};
+
+    #[test]
+    fn redoes_in_order() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches =
+            add::app().get_matches_from(vec!["add", "-d", "Test Description", "-t", "1234"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let matches = status::app().get_matches_from(vec!["status", "1234", "in-progress"]);
+        assert!(status::handler(&matches, &mut state).is_ok());
+        let matches = close::app().get_matches_from(vec!["close", "1234"]);
+        assert!(close::handler(&matches, &mut state).is_ok());
+
+        for _ in 0..2 {
+            let matches = undo::app().get_matches_from(vec!["undo"]);
+            assert!(undo::handler(&matches, &mut state).is_ok());
+        }
+        let task = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(task.task_status(), Status::Open);
+
+        let matches = app().get_matches_from(vec!["redo"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let task = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(task.task_status(), Status::InProgress);
+
+        let matches = app().get_matches_from(vec!["redo"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let task = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(task.task_status(), Status::Closed);
+    }
+
+    #[test]
+    fn a_new_change_clears_redo() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches =
+            add::app().get_matches_from(vec!["add", "-d", "Test Description", "-t", "1234"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let matches = close::app().get_matches_from(vec!["close", "1234"]);
+        assert!(close::handler(&matches, &mut state).is_ok());
+        let matches = undo::app().get_matches_from(vec!["undo"]);
+        assert!(undo::handler(&matches, &mut state).is_ok());
+
+        let matches = status::app().get_matches_from(vec!["status", "1234", "cancelled"]);
+        assert!(status::handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["redo"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NothingToRedoError)
+        ));
+    }
+}
@@ -1,28 +1,26 @@
 use clap::{App, Arg, ArgMatches};
 use colored::Colorize;
-use dialoguer::Confirm;
 use std::io;
 
 use diesel::prelude::*;
 
-use crate::domain::SuaideError;
+use crate::common::args::values_to_vec;
+use crate::common::render::TaskRenderer;
+use crate::common::selection::{confirm_selection, resolve_tasks, selection_args, Selection};
+use crate::common::storage::{get_task_outputs, update_tasks};
+use crate::domain::{SuaideError, Task, TaskChangeSet, TaskOutput};
 use crate::state::State;
 
 pub fn app<'a>() -> App<'a, 'static> {
     App::new("remove")
         .about("Delete task(s)")
-        .arg(
-            Arg::with_name("task")
-                .index(1)
-                .help("What ticket would you like to delete")
-                .takes_value(true),
-        )
+        .args(&selection_args("What tickets would you like to delete"))
         .arg(
             Arg::with_name("all")
                 .help("Delete all tasks")
                 .long("all")
                 .short("a")
-                .conflicts_with("task")
+                .conflicts_with_all(&["task", "tag", "status_filter"])
                 .takes_value(false),
         )
 }
@@ -32,51 +30,266 @@ pub fn handler<W: io::Write>(
     state: &mut State<W>,
 ) -> Result<(), SuaideError> {
     if matches.is_present("all") {
-        return confirm_and_delete_all(state.get_conn());
+        return confirm_and_delete_all(state);
     }
 
-    if let Some(task) = matches.value_of("task") {
-        let task = state.generate_ticket_id(Some(task)).unwrap();
-        return delete_single_task(&task, state.get_conn());
+    let values = values_to_vec(matches, "task");
+    let selection = resolve_tasks(&values, matches, state)?;
+    if !confirm_selection(state, &selection, "removed", matches)? {
+        return Ok(());
     }
-    Err(SuaideError::IncorrectArgs)
+    remove_tasks(&values, &selection, state)
+}
+
+fn confirm_and_delete_all<W: io::Write>(state: &mut State<W>) -> Result<(), SuaideError> {
+    use crate::schema::suaide::dsl::{deleted_at, suaide};
+
+    let prompt = format!(
+        "{} {}",
+        "Are you sure?".bold(),
+        "Every task will be moved to the trash".red().italic()
+    );
+    if state.prompter().confirm(&prompt, false)? {
+        let removed = suaide
+            .filter(deleted_at.is_null())
+            .load::<Task>(state.get_conn())?;
+        let output = get_task_outputs(&removed, state.get_conn())?;
+        move_to_trash("remove --all", &removed, state)?;
+        let message = "Removed all tasks".red().to_string();
+        TaskRenderer::new(state.get_output_format(), false).render_message(
+            state.writer(),
+            &output,
+            &message,
+        )?;
+    }
+    Ok(())
 }
 
-fn confirm_and_delete_all(db_conn: &SqliteConnection) -> Result<(), SuaideError> {
-    use crate::schema::suaide::dsl::suaide;
-
-    let mut confirmation = Confirm::new();
-    confirmation.default(false);
-    if confirmation
-        .with_prompt(format!(
-            "{} {}",
-            "Are you sure?".bold(),
-            "This is irreversible".red().italic()
-        ))
-        .interact()?
-    {
-        diesel::delete(suaide).execute(db_conn)?;
-        println!("{}", "Removed all tasks".red());
+fn remove_tasks<W: io::Write>(
+    values: &[String],
+    selection: &Selection,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let output = get_task_outputs(&selection.tasks, state.get_conn())?;
+    move_to_trash("remove", &selection.tasks, state)?;
+
+    let renderer = TaskRenderer::new(state.get_output_format(), false);
+    if !selection.bulk {
+        // A single task is reported the way it was asked for
+        let task = state.generate_ticket_id(Some(&values[0])).unwrap();
+        let message = if selection.tasks[0].ticket.as_deref() == Some(task.as_str()) {
+            format!("[{}]: Task {}", "Removed".red(), task)
+        } else {
+            format!("[{}]: Task #{}", "Removed".red(), values[0])
+        };
+        return renderer.render_message(state.writer(), &output[0], &message);
     }
+    let message: Vec<String> = selection
+        .tasks
+        .iter()
+        .map(|task| {
+            format!(
+                "[{}]: Task {}",
+                "Removed".red(),
+                TaskOutput::reference(task)
+            )
+        })
+        .collect();
+    renderer.render_message(state.writer(), &output, &message.join("\n"))
+}
+
+/// Tasks are only soft deleted so they can be restored from the trash until
+/// it's emptied
+fn move_to_trash<W: io::Write>(
+    command: &str,
+    tasks: &[Task],
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let now = state.now().timestamp();
+    update_tasks(command, tasks, now, state.get_conn(), |task| {
+        let mut change_set = TaskChangeSet::default();
+        change_set.set_deleted(task, Some(now));
+        change_set
+    })?;
     Ok(())
 }
 
-fn delete_single_task(task: &str, db_conn: &SqliteConnection) -> Result<(), SuaideError> {
-    use crate::schema::suaide::dsl::{suaide, ticket};
+#[cfg(test)]
+mod test_remove_app {
+    use super::*;
+
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn removes_task_by_ticket() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["remove", "1234"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        use crate::schema::suaide::dsl::{deleted_at, suaide};
+        let count: i64 = suaide
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(count, 0);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Removed"));
+        assert!(data.ends_with("]: Task 1234\n"));
+    }
+
+    #[test]
+    fn removes_task_by_id() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["remove", "1"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.ends_with("]: Task #1\n"));
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["remove", "1234"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["id"], 1);
+        assert_eq!(output["ticket"], "1234");
+    }
+
+    #[test]
+    fn removes_a_range_of_tasks() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Other Description")
+            .insert(state.get_conn());
 
-    if let Ok(result) = diesel::delete(suaide.filter(ticket.eq(Some(task)))).execute(db_conn) {
-        if result == 1 {
-            println!("[{}]: Task {}", "Removed".red(), task);
-            return Ok(());
-        }
+        let matches = app().get_matches_from(vec!["remove", "1..2", "-y"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        use crate::schema::suaide::dsl::{deleted_at, suaide};
+        let count: i64 = suaide
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(count, 0);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("]: Task 1234\n"));
+        assert!(data.ends_with("]: Task #2\n"));
     }
-    if let Ok(num) = task.parse::<i32>() {
-        if let Ok(result) = diesel::delete(suaide.find(num)).execute(db_conn) {
-            if result == 1 {
-                println!("[{}]: Task #{}", "Removed".red(), task);
-                return Ok(());
-            }
-        }
+
+    #[test]
+    fn keeps_the_tasks_when_the_preview_is_declined() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(false)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Other Description")
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["remove", "1234", "2"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        use crate::schema::suaide::dsl::{deleted_at, suaide};
+        let count: i64 = suaide
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.starts_with("2 task(s) will be removed:\n"));
+    }
+
+    #[test]
+    fn removes_all_tasks_when_confirmed() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(true)));
+
+        let matches = app().get_matches_from(vec!["remove", "--all"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        use crate::schema::suaide::dsl::{deleted_at, suaide};
+        let count: i64 = suaide
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(count, 0);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Removed all tasks"));
+    }
+
+    #[test]
+    fn keeps_all_tasks_when_not_confirmed() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(false)));
+
+        let matches = app().get_matches_from(vec!["remove", "--all"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        use crate::schema::suaide::dsl::{deleted_at, suaide};
+        let count: i64 = suaide
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(state.get_conn())
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(writer.is_empty());
+    }
+
+    #[test]
+    fn should_error_with_not_found() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let matches = app().get_matches_from(vec!["remove", "1234"]);
+        let result = handler(&matches, &mut state).unwrap_err();
+        match result {
+            SuaideError::NotFound => {}
+            _ => panic!("Expected Not Found error"),
+        };
+        assert!(writer.is_empty());
     }
-    Err(SuaideError::NotFound)
 }
@@ -0,0 +1,278 @@
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
+use colored::Colorize;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{get_task_by_id, get_time_entries_between};
+use crate::common::time::{calculate_duration_from_timeframe, format_duration};
+use crate::domain::{SuaideError, TaskOutput, Timeframe};
+use crate::state::State;
+
+const NO_TICKET: &str = "(no ticket)";
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("report")
+        .about("Summarise your tasks")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("time")
+                .about("Summarise the time tracked against each task and ticket, defaults to this week")
+                .arg(
+                    Arg::with_name("today")
+                        .long("today")
+                        .help("Time tracked today"),
+                )
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .help("Time tracked this week"),
+                )
+                .arg(
+                    Arg::with_name("lastweek")
+                        .long("lastweek")
+                        .help("Time tracked last week"),
+                )
+                .arg(
+                    Arg::with_name("month")
+                        .long("month")
+                        .help("Time tracked this month"),
+                )
+                .group(ArgGroup::with_name("timeframe").args(&["today", "week", "lastweek", "month"])),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    match matches.subcommand() {
+        ("time", Some(matches)) => time(matches, state),
+        _ => Err(SuaideError::SubCommandNotFound),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TaskTime {
+    id: i32,
+    ticket: Option<String>,
+    description: String,
+    seconds: i64,
+    #[serde(skip)]
+    reference: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TicketTime {
+    ticket: Option<String>,
+    seconds: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct TimeReport {
+    tasks: Vec<TaskTime>,
+    tickets: Vec<TicketTime>,
+    total_seconds: i64,
+}
+
+fn time<W: io::Write>(matches: &ArgMatches, state: &mut State<W>) -> Result<(), SuaideError> {
+    let timeframe = ["today", "week", "lastweek", "month"]
+        .iter()
+        .find(|t| matches.is_present(t))
+        .map_or(Timeframe::Week, |t| Timeframe::from(*t));
+    let (start, end) = calculate_duration_from_timeframe(state.now().date(), timeframe);
+    let now = state.now().timestamp();
+
+    let mut per_task: BTreeMap<i32, i64> = BTreeMap::new();
+    for entry in get_time_entries_between(start, end, state.get_conn())? {
+        *per_task.entry(entry.task_id).or_default() += entry.seconds_within(start, end, now);
+    }
+
+    let mut tasks = Vec::with_capacity(per_task.len());
+    let mut per_ticket: BTreeMap<Option<String>, i64> = BTreeMap::new();
+    for (task, seconds) in per_task {
+        let task = get_task_by_id(task, state.get_conn())?;
+        *per_ticket.entry(task.ticket.clone()).or_default() += seconds;
+        tasks.push(TaskTime {
+            reference: TaskOutput::reference(&task),
+            id: task.id,
+            ticket: task.ticket,
+            description: task.description,
+            seconds,
+        });
+    }
+    tasks.sort_by_key(|t| Reverse(t.seconds));
+
+    let mut tickets: Vec<TicketTime> = per_ticket
+        .into_iter()
+        .map(|(ticket, seconds)| TicketTime { ticket, seconds })
+        .collect();
+    tickets.sort_by_key(|t| Reverse(t.seconds));
+
+    let report = TimeReport {
+        total_seconds: tasks.iter().map(|t| t.seconds).sum(),
+        tasks,
+        tickets,
+    };
+    let message = format_time_report(&report);
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &report,
+        &message,
+    )
+}
+
+fn format_time_report(report: &TimeReport) -> String {
+    let mut lines = vec!["=== Tasks ===".bold().to_string()];
+    for task in &report.tasks {
+        lines.push(format!(
+            "{}\t{} - {}",
+            format_duration(task.seconds),
+            task.reference,
+            task.description
+        ));
+    }
+    lines.push(String::new());
+    lines.push("=== Tickets ===".bold().to_string());
+    for ticket in &report.tickets {
+        lines.push(format!(
+            "{}\t{}",
+            format_duration(ticket.seconds),
+            ticket.ticket.as_deref().unwrap_or(NO_TICKET)
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "{}\t{}",
+        format_duration(report.total_seconds),
+        "Total".bold()
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test_report_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    fn state_on_monday<W: io::Write>(writer: W) -> (State<W>, DateTime<Local>) {
+        let mut state = State::new(writer).unwrap();
+        let now = Local.ymd(2020, 11, 16).and_hms(17, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+        (state, now)
+    }
+
+    #[test]
+    fn aggregates_per_task_and_ticket() {
+        let mut writer = Vec::new();
+        let (mut state, now) = state_on_monday(&mut writer);
+        let now = now.timestamp();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+        test_helpers::insert_entry(1, now - 7200, Some(now - 3600), state.get_conn());
+        test_helpers::insert_entry(1, now - 1800, Some(now - 900), state.get_conn());
+        test_helpers::insert_entry(2, now - 600, None, state.get_conn());
+        // Last week, outside of the window
+        test_helpers::insert_entry(
+            2,
+            now - 8 * 86400,
+            Some(now - 8 * 86400 + 3600),
+            state.get_conn(),
+        );
+
+        let matches = app().get_matches_from(vec!["report", "time", "--week"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("1h 15m\t1234 - Test Description"));
+        assert!(data.contains("0h 10m\t#2 - Test Description"));
+        assert!(data.contains("0h 10m\t(no ticket)"));
+        assert!(data.contains("1h 25m\t"));
+    }
+
+    #[test]
+    fn clips_entries_to_the_window() {
+        let mut writer = Vec::new();
+        let (mut state, now) = state_on_monday(&mut writer);
+        state.set_output_format(OutputFormat::Json);
+        let midnight = Local.ymd(2020, 11, 16).and_hms(0, 0, 1).timestamp();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        test_helpers::insert_entry(1, midnight - 3600, Some(midnight + 1800), state.get_conn());
+        test_helpers::insert_entry(1, now.timestamp() - 60, None, state.get_conn());
+
+        let matches = app().get_matches_from(vec!["report", "time", "--today"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["total_seconds"], 1860);
+        assert_eq!(output["tasks"][0]["ticket"], "1234");
+        assert_eq!(output["tickets"][0]["seconds"], 1860);
+    }
+
+    #[test]
+    fn skips_tasks_in_the_trash() {
+        let mut writer = Vec::new();
+        let (mut state, now) = state_on_monday(&mut writer);
+        state.set_output_format(OutputFormat::Json);
+        let now = now.timestamp();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .ticket("5678")
+            .deleted_at(now)
+            .insert(state.get_conn());
+        test_helpers::insert_entry(1, now - 3600, Some(now - 1800), state.get_conn());
+        test_helpers::insert_entry(2, now - 1800, Some(now - 900), state.get_conn());
+
+        let matches = app().get_matches_from(vec!["report", "time", "--today"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["total_seconds"], 1800);
+        assert_eq!(output["tasks"].as_array().map(Vec::len), Some(1));
+        assert_eq!(output["tickets"].as_array().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn empty_report() {
+        let mut writer = Vec::new();
+        let (mut state, _) = state_on_monday(&mut writer);
+        state.set_output_format(OutputFormat::Json);
+
+        let matches = app().get_matches_from(vec!["report", "time"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["total_seconds"], 0);
+        assert_eq!(output["tasks"].as_array().map(Vec::len), Some(0));
+    }
+}
+
+#[cfg(test)]
+mod test_helpers {
+    use diesel::prelude::*;
+
+    pub fn insert_entry(task: i32, from: i64, to: Option<i64>, db_conn: &SqliteConnection) {
+        use crate::schema::time_entries::dsl::*;
+
+        diesel::insert_into(time_entries)
+            .values((task_id.eq(task), started.eq(from), stopped.eq(to)))
+            .execute(db_conn)
+            .expect("Insert should be successful");
+    }
+}
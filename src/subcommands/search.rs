@@ -0,0 +1,285 @@
+use clap::{App, Arg, ArgMatches};
+use std::collections::HashMap;
+use std::io;
+
+use diesel::prelude::*;
+
+use crate::common::args::{
+    duration_arg, values_to_vec, window_from_matches, STATUS_FILTERS, TIMEFRAMES,
+};
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    apply_deleted_filter, apply_project_filter, apply_status_filter, get_task_outputs,
+    search_tasks, window_query,
+};
+use crate::domain::{SearchHit, SearchResultOutput, Status, SuaideError, Task};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("search")
+        .about("Search the tickets, descriptions and notes of your tasks")
+        .arg(
+            Arg::with_name("query")
+                .index(1)
+                .required(true)
+                .multiple(true)
+                .help("The words to search for, a trailing * matches any word starting with it")
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("raw").long("raw").help(
+            "Use the query as is with the full SQLite FTS5 query syntax, such as OR and NEAR",
+        ))
+        .arg(
+            Arg::with_name("timeframe")
+                .long("timeframe")
+                .short("t")
+                .help("Only search tasks worked on, or due, within this timeframe")
+                .conflicts_with("duration")
+                .default_value("all")
+                .possible_values(&TIMEFRAMES)
+                .takes_value(true),
+        )
+        .arg(duration_arg())
+        .arg(
+            Arg::with_name("status")
+                .long("status")
+                .short("s")
+                .help("Only search tasks with this status, can be repeated or comma separated")
+                .multiple(true)
+                .number_of_values(1)
+                .use_delimiter(true)
+                .possible_values(&STATUS_FILTERS)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("limit")
+                .long("limit")
+                .short("n")
+                .help("The most results to show")
+                .default_value("20")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .help("Provide additional information about each task"),
+        )
+        .arg(
+            Arg::with_name("all_projects")
+                .long("all-projects")
+                .help("Search tasks from every project, not just the active one"),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let is_verbose = matches.is_present("verbose");
+    let words = values_to_vec(matches, "query");
+    let query = if matches.is_present("raw") {
+        words.join(" ")
+    } else {
+        SearchHit::match_query(&words)
+    };
+    if query.trim().is_empty() {
+        return Err(SuaideError::IncorrectArgs);
+    }
+    let limit = matches
+        .value_of("limit")
+        .expect("has default value")
+        .parse::<usize>()
+        .map_err(|_| SuaideError::IncorrectArgs)?;
+    let statuses: Vec<Status> = values_to_vec(matches, "status")
+        .iter()
+        .flat_map(|s| Status::from_filter(s))
+        .collect();
+    let project = if matches.is_present("all_projects") {
+        None
+    } else {
+        state.get_project().map(|p| p.id)
+    };
+    let now = state.now();
+    let (start, end, by_due_date) = window_from_matches(matches, now)?;
+
+    let hits = search_tasks(&query, state.get_conn())?;
+    let ids: Vec<i32> = hits.iter().map(|hit| hit.task_id).collect();
+
+    use crate::schema::suaide::dsl::id;
+
+    let query = apply_deleted_filter(window_query(start, end, by_due_date));
+    let query = apply_project_filter(query, project);
+    let tasks = apply_status_filter(query, &statuses)
+        .filter(id.eq_any(ids))
+        .load::<Task>(state.get_conn())?;
+
+    // The tasks are put back in the order they were ranked in
+    let mut tasks: HashMap<i32, Task> = tasks.into_iter().map(|t| (t.id, t)).collect();
+    let (hits, tasks): (Vec<&SearchHit>, Vec<Task>) = hits
+        .iter()
+        .filter_map(|hit| tasks.remove(&hit.task_id).map(|task| (hit, task)))
+        .take(limit)
+        .unzip();
+    let results: Vec<SearchResultOutput> = get_task_outputs(&tasks, state.get_conn())?
+        .into_iter()
+        .zip(hits)
+        .map(|(output, hit)| SearchResultOutput::new(output, hit))
+        .collect();
+
+    TaskRenderer::new(state.get_output_format(), is_verbose)
+        .with_now(now)
+        .render_search_results(state.writer(), &results)
+}
+
+#[cfg(test)]
+mod test_search_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::storage::add_note_to_task;
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::state::State;
+    use colored::Colorize;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn finds_tasks_by_description_best_match_first() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("TASK-1")
+            .description("Fix the login page")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("TASK-2")
+            .description("Update the docs")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("LOGIN-3")
+            .description("Login audit")
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["search", "login"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(!data.contains("Update the docs"));
+        let audit = data.find("Login audit").expect("should be found");
+        let page = data.find("Fix the login page").expect("should be found");
+        assert!(audit < page);
+        assert!(data.contains(&format!("{}", "login".yellow().bold())));
+    }
+
+    #[test]
+    fn matches_tickets_and_notes_as_they_change() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+
+        testing::task()
+            .ticket("TASK-12")
+            .description("Rotate the keys")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Renew certificates")
+            .insert(state.get_conn());
+        add_note_to_task(
+            2,
+            "Waiting on the vault team".to_string(),
+            20000,
+            state.get_conn(),
+        )
+        .unwrap();
+
+        let matches = app().get_matches_from(vec!["search", "TASK-12"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let matches = app().get_matches_from(vec!["search", "vau*"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let mut lines = data.lines();
+        let output: serde_json::Value =
+            serde_json::from_str(lines.next().unwrap()).expect("should be valid JSON");
+        assert_eq!(output.as_array().map(Vec::len), Some(1));
+        assert_eq!(output[0]["ticket"], "TASK-12");
+        assert!(output[0]["score"].as_f64().unwrap() > 0.0);
+
+        let output: serde_json::Value =
+            serde_json::from_str(lines.next().unwrap()).expect("should be valid JSON");
+        assert_eq!(output[0]["id"], 2);
+        assert_eq!(output[0]["snippet"], "Waiting on the vault team");
+    }
+
+    #[test]
+    fn leaves_out_removed_tasks_and_those_outside_the_timeframe() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 10, 1).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        testing::task()
+            .ticket("1")
+            .description("Deploy old service")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("2")
+            .description("Deploy new service")
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("3")
+            .description("Deploy removed service")
+            .insert(state.get_conn());
+
+        use crate::schema::suaide::dsl::{deleted_at, opened, suaide};
+        diesel::update(suaide.find(2))
+            .set(opened.eq(now.timestamp()))
+            .execute(state.get_conn())
+            .unwrap();
+        diesel::update(suaide.find(3))
+            .set((opened.eq(now.timestamp()), deleted_at.eq(now.timestamp())))
+            .execute(state.get_conn())
+            .unwrap();
+
+        let matches = app().get_matches_from(vec!["search", "deploy", "--timeframe", "today"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Deploy new service"));
+        assert!(!data.contains("old service"));
+        assert!(!data.contains("removed service"));
+    }
+
+    #[test]
+    fn reports_an_invalid_raw_query() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .ticket("TASK-1")
+            .description("Fix the login page")
+            .insert(state.get_conn());
+
+        for query in &["login AND", "\"login", "owner:me"] {
+            let matches = app().get_matches_from(vec!["search", "--raw", query]);
+            assert!(matches!(
+                handler(&matches, &mut state),
+                Err(SuaideError::SearchQueryError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn quotes_each_word_of_the_query() {
+        let words = vec!["TASK-12 login*".to_string(), "say \"hi\"".to_string()];
+        assert_eq!(
+            SearchHit::match_query(&words),
+            "\"TASK-12\" \"login\"* \"say\" \"\"\"hi\"\"\""
+        );
+    }
+}
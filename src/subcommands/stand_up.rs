@@ -1,12 +1,17 @@
-use chrono::Local;
 use clap::{App, Arg, ArgMatches};
-use colored::Colorize;
+use std::collections::HashSet;
 use std::io;
 
 use diesel::prelude::*;
 
-use crate::common::time::calculate_duration_from_timeframe;
-use crate::domain::{Status, SuaideError, Task, Timeframe};
+use crate::common::args::{tags_arg, values_to_vec};
+use crate::common::render::{render_template, TaskRenderer};
+use crate::common::storage::{
+    apply_deleted_filter, apply_project_filter, apply_tag_filters, get_status_changes_between,
+    get_task_outputs,
+};
+use crate::common::time::{calculate_duration_from_timeframe, calculate_standup_lookback};
+use crate::domain::{OutputFormat, SortBy, StandupFormat, Status, SuaideError, Task, Timeframe};
 use crate::state::State;
 
 pub fn app<'a>() -> App<'a, 'static> {
@@ -18,6 +23,44 @@ pub fn app<'a>() -> App<'a, 'static> {
                 .short("v")
                 .help("Provide additional information about each task"),
         )
+        .arg(tags_arg(
+            "tag",
+            "Only include tasks with this label, can be repeated or comma separated",
+        ))
+        .arg(
+            tags_arg(
+                "exclude_tag",
+                "Exclude tasks with this label, can be repeated or comma separated",
+            )
+            .long("exclude-tag"),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .long("sort")
+                .help("How to order the tasks, defaults to status then priority")
+                .possible_values(&["status", "priority", "opened", "closed", "due", "ticket"])
+                .default_value("status")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("notes")
+                .long("notes")
+                .short("n")
+                .help("Include the notes added to each task since the previous working day"),
+        )
+        .arg(
+            Arg::with_name("all_projects")
+                .long("all-projects")
+                .help("Include tasks from every project, not just the active one"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .help("Render the report with a built-in template instead of the configured one")
+                .possible_values(&["plain", "markdown", "slack"])
+                .takes_value(true),
+        )
 }
 
 pub fn handler<W: io::Write>(
@@ -25,39 +68,433 @@ pub fn handler<W: io::Write>(
     state: &mut State<W>,
 ) -> Result<(), SuaideError> {
     let is_verbose = matches.is_present("verbose");
-    let (today_start, today_end) =
-        calculate_duration_from_timeframe(Local::now().date(), Timeframe::Today);
+    let included_tags = values_to_vec(matches, "tag");
+    let excluded_tags = values_to_vec(matches, "exclude_tag");
+    let project = if matches.is_present("all_projects") {
+        None
+    } else {
+        state.get_project().map(|p| p.id)
+    };
+    let today = state.now().date();
+    let (today_start, today_end) = calculate_duration_from_timeframe(today, Timeframe::Today);
+    let working_days = state.get_settings().get_working_days()?;
+    let holidays = state.get_settings().get_holidays()?;
     let (yesterday_start, yesterday_end) =
-        calculate_duration_from_timeframe(Local::now().date(), Timeframe::Yesterday);
+        calculate_standup_lookback(today, &working_days, &holidays);
+
+    // Tasks that changed status during the look back were worked on, while
+    // tasks that only moved into progress since then weren't
+    let changes = get_status_changes_between(yesterday_start, today_end, state.get_conn())?;
+    let worked_on: HashSet<i32> = changes
+        .iter()
+        .filter(|e| e.created <= yesterday_end)
+        .map(|e| e.task_id)
+        .collect();
+    let started_since: HashSet<i32> = changes
+        .iter()
+        .filter(|e| e.created > yesterday_end)
+        .filter(|e| e.new_status() == Some(Status::InProgress))
+        .map(|e| e.task_id)
+        .filter(|task| !worked_on.contains(task))
+        .collect();
 
-    use crate::schema::suaide::dsl::{closed, opened, status, suaide};
+    use crate::schema::suaide::dsl::{closed, id, opened, status, suaide};
 
-    let mut today = suaide
+    let today = suaide
+        .into_boxed()
         .filter(status.le(Status::InProgress as i16))
         .or_filter(
             status
                 .le(Status::Closed as i16)
                 .and(closed.between(today_start, today_end)),
         )
-        .load::<Task>(state.get_conn())?;
+        .filter(opened.le(today_end));
+    let today = apply_project_filter(apply_deleted_filter(today), project);
+    let mut today =
+        apply_tag_filters(today, &included_tags, &excluded_tags).load::<Task>(state.get_conn())?;
 
-    let mut yesterday = suaide
+    let yesterday = suaide
+        .into_boxed()
         .filter(status.eq(Status::Closed as i16))
         .filter(closed.between(yesterday_start, yesterday_end))
         .or_filter(status.eq(Status::InProgress as i16))
         .filter(opened.lt(yesterday_end))
+        .or_filter(id.eq_any(worked_on))
+        .filter(id.ne_all(started_since));
+    let yesterday = apply_project_filter(apply_deleted_filter(yesterday), project);
+    let mut yesterday = apply_tag_filters(yesterday, &included_tags, &excluded_tags)
+        .load::<Task>(state.get_conn())?;
+
+    let blockers = suaide
+        .into_boxed()
+        .filter(status.eq(Status::Blocked as i16))
+        .filter(opened.le(today_end));
+    let blockers = apply_project_filter(apply_deleted_filter(blockers), project);
+    let mut blockers = apply_tag_filters(blockers, &included_tags, &excluded_tags)
         .load::<Task>(state.get_conn())?;
 
-    yesterday.sort();
-    today.sort();
+    let sort = SortBy::from(matches.value_of("sort").expect("has default value"));
+    sort.sort(&mut yesterday);
+    sort.sort(&mut today);
+    sort.sort(&mut blockers);
+
+    let mut sections = [
+        ("Yesterday", get_task_outputs(&yesterday, state.get_conn())?),
+        ("Today", get_task_outputs(&today, state.get_conn())?),
+        ("Blockers", get_task_outputs(&blockers, state.get_conn())?),
+    ];
+    // Only notes added within the stand-up window are relevant
+    let show_notes = matches.is_present("notes");
+    for (_, tasks) in sections.iter_mut() {
+        for task in tasks.iter_mut() {
+            task.notes.retain(|note| {
+                show_notes
+                    && note.created.timestamp() >= yesterday_start
+                    && note.created.timestamp() <= today_end
+            });
+        }
+    }
+    let template = match matches.value_of("format") {
+        Some(format) => Some(StandupFormat::from(format).template()),
+        None => state.get_settings().get_standup_template()?,
+    };
+    match template {
+        Some(template) if state.get_output_format() == OutputFormat::Text => {
+            let ticket_url = state.get_settings().get_ticket_url().map(String::from);
+            render_template(state.writer(), &template, &sections, ticket_url.as_deref())
+        }
+        _ => TaskRenderer::new(state.get_output_format(), is_verbose)
+            .with_now(state.now())
+            .with_notes(show_notes)
+            .render_sections(state.writer(), &sections),
+    }
+}
+
+#[cfg(test)]
+mod test_stand_up_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::storage::record_events;
+    use crate::common::testing::{self, in_time_zone};
+    use crate::domain::{AddTaskEvent, OutputFormat, TaskEvent};
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn splits_tasks_into_sections() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("Open Task")
+            .insert(state.get_conn());
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Closed Task")
+            .closed(20000)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["standup"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let today_idx = data.find("Today").expect("should have a today section");
+        let (yesterday, today) = data.split_at(today_idx);
+
+        assert!(yesterday.contains("Yesterday"));
+        assert!(yesterday.contains("In Progress Task"));
+        assert!(!yesterday.contains("Open Task"));
+        assert!(!yesterday.contains("Closed Task"));
+
+        assert!(today.contains("Open Task"));
+        assert!(today.contains("In Progress Task"));
+        assert!(!today.contains("Closed Task"));
+    }
+
+    fn state_at<W: std::io::Write>(writer: W, now: DateTime<Local>) -> State<W> {
+        let mut state = State::new(writer).unwrap();
+        state.set_clock(Box::new(FixedClock::new(now)));
+        state
+    }
+
+    fn sections(data: &str) -> (&str, &str) {
+        let today_idx = data.find("Today").expect("should have a today section");
+        data.split_at(today_idx)
+    }
+
+    #[test]
+    fn yesterday_over_month_boundary() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 10, 1).and_hms(9, 0, 0));
+
+        let closed_yesterday = Local.ymd(2020, 9, 30).and_hms(17, 0, 0).timestamp();
+        let closed_before = Local.ymd(2020, 9, 29).and_hms(17, 0, 0).timestamp();
+        testing::task()
+            .description("Closed Yesterday")
+            .closed(closed_yesterday)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Closed Before")
+            .closed(closed_before)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["standup"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let (yesterday, today) = sections(data);
+        assert!(yesterday.contains("Closed Yesterday"));
+        assert!(!today.contains("Closed Yesterday"));
+        assert!(!data.contains("Closed Before"));
+    }
+
+    #[test]
+    fn closed_today_is_in_today() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 10, 1).and_hms(9, 0, 0));
+
+        let closed_today = Local.ymd(2020, 10, 1).and_hms(8, 0, 0).timestamp();
+        testing::task()
+            .description("Closed Today")
+            .closed(closed_today)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["standup"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let (yesterday, today) = sections(data);
+        assert!(!yesterday.contains("Closed Today"));
+        assert!(today.contains("Closed Today"));
+    }
+
+    #[test]
+    fn yesterday_over_dst_transition() {
+        if !in_time_zone(
+            module_path!(),
+            "yesterday_over_dst_transition",
+            "Europe/London",
+        ) {
+            return;
+        }
+        // Clocks went back on the 25th of October 2020 within Europe
+        assert_ne!(
+            Local.ymd(2020, 10, 24).offset(),
+            Local.ymd(2020, 10, 26).offset()
+        );
+
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 10, 26).and_hms(9, 0, 0));
+
+        let closed_late = Local.ymd(2020, 10, 25).and_hms(23, 30, 0).timestamp();
+        let opened_late = Local.ymd(2020, 10, 26).and_hms(0, 30, 0).timestamp();
+        testing::task()
+            .description("Closed Late")
+            .closed(closed_late)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Opened After Midnight")
+            .opened(opened_late)
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["standup"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let (yesterday, today) = sections(data);
+        assert!(yesterday.contains("Closed Late"));
+        assert!(!yesterday.contains("Opened After Midnight"));
+        assert!(today.contains("Opened After Midnight"));
+    }
+
+    #[test]
+    fn monday_looks_back_to_friday() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 10, 5).and_hms(9, 0, 0));
+
+        let closed_friday = Local.ymd(2020, 10, 2).and_hms(17, 0, 0).timestamp();
+        let closed_thursday = Local.ymd(2020, 10, 1).and_hms(17, 0, 0).timestamp();
+        testing::task()
+            .description("Closed Friday")
+            .closed(closed_friday)
+            .insert(state.get_conn());
+        testing::task()
+            .description("Closed Thursday")
+            .closed(closed_thursday)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["standup"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let (yesterday, _) = sections(data);
+        assert!(yesterday.contains("Closed Friday"));
+        assert!(!data.contains("Closed Thursday"));
+    }
+
+    #[test]
+    fn uses_status_transition_times() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 11, 24).and_hms(9, 0, 0));
+
+        let blocked_yesterday = Local.ymd(2020, 11, 23).and_hms(15, 0, 0).timestamp();
+        let started_today = Local.ymd(2020, 11, 24).and_hms(8, 30, 0).timestamp();
+        testing::task()
+            .description("Blocked Yesterday")
+            .blocked("Waiting on review")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Started Today")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        let events = [
+            AddTaskEvent::new(
+                1,
+                TaskEvent::STATUS,
+                Some(Status::InProgress.key().to_string()),
+                Some(Status::Blocked.key().to_string()),
+                blocked_yesterday,
+            ),
+            AddTaskEvent::new(
+                2,
+                TaskEvent::STATUS,
+                Some(Status::Open.key().to_string()),
+                Some(Status::InProgress.key().to_string()),
+                started_today,
+            ),
+        ];
+        record_events(&events, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["standup"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let (yesterday, today) = sections(data);
+        assert!(yesterday.contains("Blocked Yesterday"));
+        assert!(!yesterday.contains("Started Today"));
+        assert!(today.contains("Started Today"));
+    }
+
+    #[test]
+    fn blocked_tasks_are_listed_as_blockers() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("Blocked Task")
+            .blocked("Waiting on review")
+            .insert(state.get_conn());
+        testing::task()
+            .description("Open Task")
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["standup"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let blockers_idx = data
+            .find("Blockers")
+            .expect("should have a blockers section");
+        let (rest, blockers) = data.split_at(blockers_idx);
+        assert!(blockers.contains("Blocked Task"));
+        assert!(blockers.contains("Waiting on review"));
+        assert!(!blockers.contains("Open Task"));
+        assert!(!rest.contains("Blocked Task"));
+    }
+
+    #[test]
+    fn includes_recent_notes() {
+        use crate::common::storage::add_note_to_task;
+
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 10, 7).and_hms(9, 0, 0));
+
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        let old = Local.ymd(2020, 10, 1).and_hms(9, 0, 0).timestamp();
+        let recent = Local.ymd(2020, 10, 6).and_hms(15, 0, 0).timestamp();
+        add_note_to_task(1, "Old note".to_string(), old, state.get_conn()).unwrap();
+        add_note_to_task(1, "Recent note".to_string(), recent, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["standup", "--notes"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Recent note"));
+        assert!(!data.contains("Old note"));
+    }
+
+    #[test]
+    fn notes_are_hidden_by_default() {
+        use crate::common::storage::add_note_to_task;
+
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+        let now = state.now().timestamp();
+        add_note_to_task(1, "Recent note".to_string(), now, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["standup", "-v"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(!data.contains("Recent note"));
+    }
+
+    #[test]
+    fn markdown_format() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["standup", "--format", "markdown"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let (yesterday, today) = sections(data);
+        assert!(yesterday.starts_with("### Yesterday\n"));
+        assert!(yesterday.contains("- **#1** In Progress Task _(In Progress)_"));
+        assert!(today.starts_with("Today\n"));
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
 
-    println!("=== {} ===", "Yesterday".bold());
-    yesterday.iter().for_each(|result| result.print(is_verbose));
-    println!();
+        testing::task()
+            .description("In Progress Task")
+            .status(Status::InProgress)
+            .insert(state.get_conn());
 
-    println!("=== {} ===", "Today".bold());
-    today.iter().for_each(|result| result.print(is_verbose));
-    println!();
+        let matches = app().get_matches_from(vec!["standup"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
 
-    Ok(())
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["yesterday"][0]["description"], "In Progress Task");
+        assert_eq!(output["today"][0]["status"], "in-progress");
+        assert_eq!(output["blockers"], serde_json::json!([]));
+    }
 }
@@ -0,0 +1,129 @@
+use clap::{App, Arg, ArgMatches};
+use colored::Colorize;
+use diesel::prelude::*;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    get_running_time_entry, get_task_by_id, get_task_output, start_time_entry, update_task,
+};
+use crate::domain::{Status, SuaideError, TaskChangeSet, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("start").about("Start a timer against a task").arg(
+        Arg::with_name("task")
+            .index(1)
+            .required(true)
+            .help("The task to start working on")
+            .takes_value(true),
+    )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    if let Some(task) = matches.value_of("task") {
+        let task = state.find_task(task)?;
+        if let Some(running) = get_running_time_entry(state.get_conn())? {
+            let running = get_task_by_id(running.task_id, state.get_conn())?;
+            return Err(SuaideError::TimerAlreadyRunningError(
+                TaskOutput::reference(&running),
+            ));
+        }
+
+        let now = state.now().timestamp();
+        let conn = state.get_conn();
+        conn.transaction::<_, SuaideError, _>(|| {
+            start_time_entry(task.id, now, conn)?;
+
+            let mut change_set = TaskChangeSet::default();
+            change_set.set_status(&task, Status::InProgress, now);
+            update_task(&task, change_set, now, conn)
+        })?;
+
+        let task = get_task_by_id(task.id, state.get_conn())?;
+        let output = get_task_output(&task, state.get_conn())?;
+        let message = format!(
+            "{} {}",
+            "Started timer for".green(),
+            TaskOutput::reference(&task)
+        );
+        return TaskRenderer::new(state.get_output_format(), false).render_message(
+            state.writer(),
+            &output,
+            &message,
+        );
+    }
+    Err(SuaideError::IncorrectArgs)
+}
+
+#[cfg(test)]
+mod test_start_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::testing;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn starts_a_timer_and_moves_the_task_in_progress() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 11, 16).and_hms(9, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["start", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let running = get_running_time_entry(state.get_conn())
+            .unwrap()
+            .expect("a timer should be running");
+        assert_eq!(running.task_id, 1);
+        assert_eq!(running.started, now.timestamp());
+
+        let task = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(task.task_status(), Status::InProgress);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Started timer for"));
+        assert!(data.contains("1234"));
+    }
+
+    #[test]
+    fn only_one_timer_can_run() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["start", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["start", "2"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::TimerAlreadyRunningError(reference)) if reference == "1234"
+        ));
+    }
+
+    #[test]
+    fn should_error_with_not_found() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["start", "1234"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NotFound)
+        ));
+    }
+}
@@ -1,40 +1,50 @@
 use clap::{App, Arg, ArgMatches};
 use std::io;
 
-use diesel::prelude::*;
-
-use crate::common::{inputs::get_state_input, storage::get_task};
+use crate::common::args::values_to_vec;
+use crate::common::inputs::{confirm_closing_parent, get_blocked_reason, get_state_input};
+use crate::common::render::TaskRenderer;
+use crate::common::selection::{confirm_selection, resolve_tasks, selection_args};
+use crate::common::storage::{get_task_outputs, update_tasks};
 use crate::domain::{Status, SuaideError, Task, TaskChangeSet};
 use crate::state::State;
 
+/// Every value accepted as the new state, given after the tasks
+const STATES: [&str; 14] = [
+    "open",
+    "o",
+    "in-progress",
+    "inprogress",
+    "progress",
+    "ip",
+    "blocked",
+    "block",
+    "b",
+    "closed",
+    "close",
+    "c",
+    "cancel",
+    "cancelled",
+];
+
 pub fn app<'a>() -> App<'a, 'static> {
     App::new("status")
         .about("Change the status of a task")
+        .args(&selection_args(
+            "The tasks to update, optionally followed by the state to update them with",
+        ))
         .arg(
-            Arg::with_name("task")
-                .index(1)
-                .help("The task to update")
-                .required(true)
+            Arg::with_name("reason")
+                .long("reason")
+                .short("r")
+                .help("Why the task is blocked, required when marking a task as blocked")
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("state")
-                .index(2)
-                .help("The state to update the task with")
-                .possible_values(&[
-                    "open",
-                    "o",
-                    "in-progress",
-                    "inprogress",
-                    "progress",
-                    "ip",
-                    "closed",
-                    "close",
-                    "c",
-                    "cancel",
-                    "cancelled",
-                ])
-                .takes_value(true),
+            Arg::with_name("force")
+                .long("force")
+                .short("f")
+                .help("Close the task even if it has open subtasks"),
         )
         .arg(
             Arg::with_name("verbose")
@@ -49,32 +59,247 @@ pub fn handler<W: io::Write>(
     state: &mut State<W>,
 ) -> Result<(), SuaideError> {
     let is_verbose = matches.is_present("verbose");
-    if let Some(task_id) = matches.value_of("task") {
-        let task_id = state.generate_ticket_id(Some(task_id)).unwrap();
-        let task = get_task(&task_id, state.get_conn())?;
-        let change_set = if let Some(state) = matches.value_of("state") {
-            let updated_status = Status::from(state);
-            generate_change_set(&task, updated_status)?
-        } else {
-            let updated_status = get_state_input(task.status.into());
-            generate_change_set(&task, updated_status)?
-        };
-
-        use crate::schema::suaide::dsl::suaide;
-
-        diesel::update(suaide.find(task.id))
-            .set(change_set)
-            .execute(state.get_conn())?;
-
-        let task = get_task(&task_id, state.get_conn())?;
-        task.print(is_verbose);
+    let (values, new_status) = split_state(values_to_vec(matches, "task"), matches);
+    let selection = resolve_tasks(&values, matches, state)?;
+    let first = &selection.tasks[0];
+    let updated_status = match new_status {
+        Some(new_status) => new_status.parse()?,
+        None => get_state_input(state, first.status.into())?,
+    };
+    let reason = match updated_status {
+        Status::Blocked => Some(get_blocked_reason(
+            state,
+            matches.value_of("reason"),
+            first.blocked_reason.clone(),
+        )?),
+        _ => None,
+    };
+    if !confirm_selection(state, &selection, "updated", matches)? {
         return Ok(());
     }
-    Err(SuaideError::IncorrectArgs)
+    if updated_status == Status::Closed {
+        let ids: Vec<i32> = selection.tasks.iter().map(|t| t.id).collect();
+        for task in &selection.tasks {
+            confirm_closing_parent(state, task, &ids, matches.is_present("force"))?;
+        }
+    }
+
+    let now = state.now().timestamp();
+    let updated = update_tasks("status", &selection.tasks, now, state.get_conn(), |task| {
+        let mut change_set = generate_change_set(task, updated_status, now);
+        if reason.is_some() {
+            change_set.set_blocked_reason(task, reason.clone());
+        }
+        change_set
+    })?;
+
+    let output = get_task_outputs(&updated, state.get_conn())?;
+    let renderer = TaskRenderer::new(state.get_output_format(), is_verbose);
+    if selection.bulk {
+        return renderer.render_tasks(state.writer(), &output);
+    }
+    renderer.render_task(state.writer(), &output[0])
 }
 
-fn generate_change_set(task: &Task, status: Status) -> Result<TaskChangeSet, SuaideError> {
+/// The state comes after the tasks, so the last value is only taken as the
+/// state when there is something else to select the tasks by
+fn split_state(mut values: Vec<String>, matches: &ArgMatches) -> (Vec<String>, Option<String>) {
+    let has_filters = matches.is_present("tag") || matches.is_present("status_filter");
+    let is_state = matches!(values.last(), Some(last) if STATES.contains(&last.as_str()));
+    if is_state && (values.len() > 1 || has_filters) {
+        let new_status = values.pop();
+        return (values, new_status);
+    }
+    (values, None)
+}
+
+fn generate_change_set(task: &Task, status: Status, now: i64) -> TaskChangeSet {
     let mut change_set = TaskChangeSet::default();
-    change_set.set_status(task, status);
-    Ok(change_set)
+    change_set.set_status(task, status, now);
+    change_set
+}
+
+#[cfg(test)]
+mod test_status_app {
+    use super::*;
+
+    use diesel::prelude::*;
+
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::testing;
+    use crate::schema::suaide::dsl::suaide;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn updates_status_from_argument() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["status", "1234", "ip"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.status, Status::InProgress as i16);
+        assert_eq!(result.closed, None);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("In Progress"));
+        assert!(data.contains("1234: Test Description"));
+    }
+
+    #[test]
+    fn updates_status_from_prompt() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        state.set_prompter(Box::new(ScriptedPrompter::default().select("Cancelled")));
+
+        let matches = app().get_matches_from(vec!["status", "1234"]);
+        let result = handler(&matches, &mut state);
+        assert!(result.is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.status, Status::Cancelled as i16);
+        assert!(result.closed.is_some());
+    }
+
+    #[test]
+    fn blocks_with_a_reason() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec![
+            "status",
+            "1234",
+            "blocked",
+            "--reason",
+            "Waiting on review",
+        ]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.status, Status::Blocked as i16);
+        assert_eq!(result.blocked_reason, Some("Waiting on review".to_string()));
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("Blocked"));
+        assert!(data.contains("Waiting on review"));
+    }
+
+    #[test]
+    fn prompts_for_a_missing_reason() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        state.set_prompter(Box::new(
+            ScriptedPrompter::default().input("Waiting on API"),
+        ));
+
+        let matches = app().get_matches_from(vec!["status", "1234", "b"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.blocked_reason, Some("Waiting on API".to_string()));
+    }
+
+    #[test]
+    fn rejects_an_empty_reason() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["status", "1234", "blocked", "-r", " "]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::BlockedReasonRequired)
+        ));
+    }
+
+    #[test]
+    fn unblocking_clears_the_reason() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["status", "1234", "blocked", "-r", "Waiting"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let matches = app().get_matches_from(vec!["status", "1234", "ip"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide
+            .find(1)
+            .first(state.get_conn())
+            .expect("This should return an Ok");
+        assert_eq!(result.status, Status::InProgress as i16);
+        assert_eq!(result.blocked_reason, None);
+    }
+
+    #[test]
+    fn updates_several_tasks_at_once() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(true)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Other Description")
+            .insert(state.get_conn());
+
+        let matches =
+            app().get_matches_from(vec!["status", "1234", "2", "blocked", "-r", "Outage"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let results: Vec<Task> = suaide.load(state.get_conn()).unwrap();
+        assert!(results.iter().all(|t| t.status == Status::Blocked as i16));
+        assert!(results
+            .iter()
+            .all(|t| t.blocked_reason == Some("Outage".to_string())));
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.starts_with("2 task(s) will be updated:\n"));
+    }
+
+    #[test]
+    fn updates_tasks_matching_the_filters() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        testing::task()
+            .description("Other Description")
+            .insert(state.get_conn());
+        let matches = app().get_matches_from(vec!["status", "2", "ip"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["status", "--status", "open", "cancel", "--yes"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let result: Task = suaide.find(1).first(state.get_conn()).unwrap();
+        assert_eq!(result.status, Status::Cancelled as i16);
+        let result: Task = suaide.find(2).first(state.get_conn()).unwrap();
+        assert_eq!(result.status, Status::InProgress as i16);
+    }
 }
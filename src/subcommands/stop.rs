@@ -0,0 +1,107 @@
+use clap::{App, ArgMatches};
+use colored::Colorize;
+use serde_json::json;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    get_running_time_entry, get_task_by_id, get_task_output, stop_time_entry,
+};
+use crate::common::time::format_duration;
+use crate::domain::{SuaideError, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("stop").about("Stop the running timer")
+}
+
+pub fn handler<W: io::Write>(
+    _matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let entry =
+        get_running_time_entry(state.get_conn())?.ok_or(SuaideError::NoTimerRunningError)?;
+    let now = state.now().timestamp();
+    stop_time_entry(&entry, now, state.get_conn())?;
+
+    let task = get_task_by_id(entry.task_id, state.get_conn())?;
+    let seconds = (now - entry.started).max(0);
+    let output = get_task_output(&task, state.get_conn())?;
+    let message = format!(
+        "{} {} after {}",
+        "Stopped".green(),
+        TaskOutput::reference(&task),
+        format_duration(seconds)
+    );
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &json!({ "task": output, "seconds": seconds }),
+        &message,
+    )
+}
+
+#[cfg(test)]
+mod test_stop_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::storage::start_time_entry;
+    use crate::common::testing;
+    use crate::domain::OutputFormat;
+    use crate::state::State;
+
+    use std::str::from_utf8;
+
+    #[test]
+    fn stops_the_running_timer() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        let now = Local.ymd(2020, 11, 16).and_hms(10, 5, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        start_time_entry(1, now.timestamp() - 3900, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["stop"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(get_running_time_entry(state.get_conn()).unwrap().is_none());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("1234"));
+        assert!(data.contains("after 1h 05m"));
+    }
+
+    #[test]
+    fn json_output() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_output_format(OutputFormat::Json);
+        let now = Local.ymd(2020, 11, 16).and_hms(10, 0, 0);
+        state.set_clock(Box::new(FixedClock::new(now)));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        start_time_entry(1, now.timestamp() - 600, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["stop"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        let output: serde_json::Value = serde_json::from_str(data).expect("should be valid JSON");
+        assert_eq!(output["seconds"], 600);
+        assert_eq!(output["task"]["ticket"], "1234");
+    }
+
+    #[test]
+    fn should_error_without_a_running_timer() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = app().get_matches_from(vec!["stop"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NoTimerRunningError)
+        ));
+    }
+}
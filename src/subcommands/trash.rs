@@ -0,0 +1,331 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use colored::Colorize;
+use std::io;
+use std::slice;
+
+use diesel::prelude::*;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{
+    get_task_by_id, get_task_output, get_task_outputs, get_trashed_task, get_trashed_tasks,
+    purge_tasks, record_journal, update_task,
+};
+use crate::common::time::parse_period;
+use crate::domain::{SuaideError, TaskChangeSet, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("trash")
+        .about("Manage removed tasks")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List the tasks in the trash")
+                .arg(
+                    Arg::with_name("verbose")
+                        .long("verbose")
+                        .short("v")
+                        .help("Provide additional information about each task"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("restore")
+                .about("Restore a task from the trash")
+                .arg(
+                    Arg::with_name("task")
+                        .index(1)
+                        .required(true)
+                        .help("The task to restore")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("empty")
+                .about("Permanently delete the tasks in the trash")
+                .arg(
+                    Arg::with_name("older_than")
+                        .long("older-than")
+                        .help("Only delete tasks removed at least this long ago, e.g. 30d or 2w")
+                        .takes_value(true),
+                ),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    match matches.subcommand() {
+        ("list", Some(matches)) => list(matches, state),
+        ("restore", Some(matches)) => restore(matches, state),
+        ("empty", Some(matches)) => empty(matches, state),
+        _ => Err(SuaideError::SubCommandNotFound),
+    }
+}
+
+fn list<W: io::Write>(matches: &ArgMatches, state: &mut State<W>) -> Result<(), SuaideError> {
+    let tasks = get_trashed_tasks(state.get_conn())?;
+    let output = get_task_outputs(&tasks, state.get_conn())?;
+    TaskRenderer::new(state.get_output_format(), matches.is_present("verbose"))
+        .with_now(state.now())
+        .render_tasks(state.writer(), &output)
+}
+
+fn restore<W: io::Write>(matches: &ArgMatches, state: &mut State<W>) -> Result<(), SuaideError> {
+    let task = matches.value_of("task").ok_or(SuaideError::IncorrectArgs)?;
+    let ticket = state.generate_ticket_id(Some(task)).unwrap();
+    let trashed = get_trashed_task(&ticket, state.get_conn())
+        .or_else(|_| get_trashed_task(task, state.get_conn()))?;
+
+    let now = state.now().timestamp();
+    let mut change_set = TaskChangeSet::default();
+    change_set.set_deleted(&trashed, None);
+    let conn = state.get_conn();
+    let restored = conn.transaction::<_, SuaideError, _>(|| {
+        update_task(&trashed, change_set, now, conn)?;
+        let restored = get_task_by_id(trashed.id, conn)?;
+        record_journal(
+            "trash restore",
+            slice::from_ref(&trashed),
+            slice::from_ref(&restored),
+            now,
+            conn,
+        )?;
+        Ok(restored)
+    })?;
+
+    let output = get_task_output(&restored, state.get_conn())?;
+    let message = format!(
+        "[{}]: Task {}",
+        "Restored".green(),
+        TaskOutput::reference(&restored)
+    );
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &output,
+        &message,
+    )
+}
+
+fn empty<W: io::Write>(matches: &ArgMatches, state: &mut State<W>) -> Result<(), SuaideError> {
+    let cutoff = match matches.value_of("older_than") {
+        Some(period) => state
+            .now()
+            .checked_sub_signed(parse_period(period)?)
+            .ok_or_else(|| SuaideError::PeriodFormatError(period.to_string()))?,
+        None => state.now(),
+    };
+    let tasks: Vec<_> = get_trashed_tasks(state.get_conn())?
+        .into_iter()
+        .filter(|t| t.deleted_at.is_some_and(|d| d <= cutoff.timestamp()))
+        .collect();
+    let renderer = TaskRenderer::new(state.get_output_format(), false);
+    if tasks.is_empty() {
+        return renderer.render_message(state.writer(), &tasks, "The trash is already empty");
+    }
+
+    let prompt = format!(
+        "{} {}",
+        format!("Permanently delete {} task(s)?", tasks.len()).bold(),
+        "This is irreversible".red().italic()
+    );
+    if state.prompter().confirm(&prompt, false)? {
+        let output = get_task_outputs(&tasks, state.get_conn())?;
+        let ids: Vec<i32> = tasks.iter().map(|t| t.id).collect();
+        purge_tasks(&ids, state.get_conn())?;
+        let message = format!("{} {} task(s)", "Permanently deleted".red(), ids.len());
+        renderer.render_message(state.writer(), &output, &message)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_trash_app {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::common::clock::FixedClock;
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::storage::{add_recurrence, get_recurrences, get_task};
+    use crate::common::testing;
+    use crate::domain::RecurrenceRule;
+    use crate::schema::recurrences;
+    use crate::state::State;
+    use crate::subcommands::{add, list, remove, undo};
+
+    use std::str::from_utf8;
+
+    fn state_at<W: io::Write>(writer: W, now: DateTime<Local>) -> State<W> {
+        let mut state = State::new(writer).unwrap();
+        state.set_clock(Box::new(FixedClock::new(now)));
+        state
+    }
+
+    #[test]
+    fn removed_tasks_are_hidden_until_restored() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 12, 7).and_hms(9, 0, 0));
+
+        testing::task().ticket("1234").insert(state.get_conn());
+        let matches = remove::app().get_matches_from(vec!["remove", "1234"]);
+        assert!(remove::handler(&matches, &mut state).is_ok());
+        assert!(matches!(
+            get_task("1234", state.get_conn()),
+            Err(SuaideError::NotFound)
+        ));
+
+        let matches = app().get_matches_from(vec!["trash", "restore", "1234"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let task = get_task("1234", state.get_conn()).expect("task should be restored");
+        assert_eq!(task.deleted_at, None);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.ends_with("]: Task 1234\n"));
+    }
+
+    #[test]
+    fn lists_removed_tasks() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 12, 7).and_hms(9, 0, 0));
+
+        testing::task()
+            .ticket("1234")
+            .deleted_at(10000)
+            .insert(state.get_conn());
+        testing::task().ticket("5678").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["trash", "list"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        let matches = list::app().get_matches_from(vec!["list", "all"]);
+        assert!(list::handler(&matches, &mut state).is_ok());
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert_eq!(data.matches("1234").count(), 1);
+        assert_eq!(data.matches("5678").count(), 1);
+        let (trash, listed) = data.split_at(data.find("5678").unwrap());
+        assert!(trash.contains("1234"));
+        assert!(!listed.contains("1234"));
+    }
+
+    #[test]
+    fn empties_tasks_older_than_the_period() {
+        let mut writer = Vec::new();
+        let now = Local.ymd(2020, 12, 7).and_hms(9, 0, 0);
+        let mut state = state_at(&mut writer, now);
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(true)));
+
+        let old = (now - chrono::Duration::days(45)).timestamp();
+        let recent = (now - chrono::Duration::days(2)).timestamp();
+        testing::task()
+            .ticket("1234")
+            .deleted_at(old)
+            .insert(state.get_conn());
+        testing::task()
+            .ticket("5678")
+            .deleted_at(recent)
+            .insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["trash", "empty", "--older-than", "30d"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let trashed = get_trashed_tasks(state.get_conn()).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].ticket, Some("5678".to_string()));
+
+        // The ticket is free to be used again once the task is gone for good
+        let matches = add::app().get_matches_from(vec!["add", "-d", "Again", "-t", "1234"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let matches = add::app().get_matches_from(vec!["add", "-d", "Again", "-t", "5678"]);
+        assert!(matches!(
+            add::handler(&matches, &mut state),
+            Err(SuaideError::TicketInTrashError(ticket)) if ticket == "5678"
+        ));
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("1 task(s)"));
+    }
+
+    #[test]
+    fn emptied_tasks_cant_be_undone() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 12, 7).and_hms(9, 0, 0));
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(true)));
+
+        let matches = add::app().get_matches_from(vec!["add", "-d", "first", "-t", "T-1"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let matches = remove::app().get_matches_from(vec!["remove", "T-1"]);
+        assert!(remove::handler(&matches, &mut state).is_ok());
+        let matches = app().get_matches_from(vec!["trash", "empty"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let matches = undo::app().get_matches_from(vec!["undo"]);
+        assert!(matches!(
+            undo::handler(&matches, &mut state),
+            Err(SuaideError::NothingToUndoError)
+        ));
+        assert!(matches!(
+            get_task("T-1", state.get_conn()),
+            Err(SuaideError::NotFound)
+        ));
+        assert!(get_trashed_tasks(state.get_conn()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn emptying_clears_pointers_to_the_deleted_tasks() {
+        let mut writer = Vec::new();
+        let mut state = state_at(&mut writer, Local.ymd(2020, 12, 7).and_hms(9, 0, 0));
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(true)));
+
+        testing::task().ticket("T-1").insert(state.get_conn());
+        let matches = add::app().get_matches_from(vec!["add", "-d", "Child", "--parent", "T-1"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        testing::task().ticket("T-3").insert(state.get_conn());
+        testing::task().ticket("T-4").insert(state.get_conn());
+        add_recurrence(3, &RecurrenceRule::Daily, 10000, state.get_conn()).unwrap();
+        diesel::update(recurrences::table)
+            .set(recurrences::last_task_id.eq(4))
+            .execute(state.get_conn())
+            .unwrap();
+
+        for ticket in &["T-1", "T-4"] {
+            let matches = remove::app().get_matches_from(vec!["remove", ticket]);
+            assert!(remove::handler(&matches, &mut state).is_ok());
+        }
+        let matches = app().get_matches_from(vec!["trash", "empty"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        assert_eq!(get_task_by_id(2, state.get_conn()).unwrap().parent_id, None);
+        let recurrences = get_recurrences(state.get_conn()).unwrap();
+        assert_eq!(recurrences.len(), 1);
+        assert_eq!(recurrences[0].last_task_id, 3);
+    }
+
+    #[test]
+    fn invalid_period() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        for period in &["soon", "30é", "9999999999w"] {
+            let matches = app().get_matches_from(vec!["trash", "empty", "--older-than", period]);
+            assert!(matches!(
+                handler(&matches, &mut state),
+                Err(SuaideError::PeriodFormatError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn should_error_restoring_a_task_not_in_the_trash() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["trash", "restore", "1234"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+use clap::{App, ArgMatches};
+use colored::Colorize;
+use serde_json::json;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{apply_journal_entry, get_journal_entry, get_task_outputs};
+use crate::domain::{SuaideError, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("undo")
+        .about("Undo the last add, edit, status, close or remove")
+        .after_help(
+            "A task's own fields and any tags edit changed are restored, its notes and \
+             dependencies are left as they are unless undoing an add deletes the task",
+        )
+}
+
+pub fn handler<W: io::Write>(
+    _matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    let entry =
+        get_journal_entry(false, state.get_conn())?.ok_or(SuaideError::NothingToUndoError)?;
+    let tasks = apply_journal_entry(&entry, true, state.get_conn())?;
+
+    let references: Vec<String> = tasks.iter().map(TaskOutput::reference).collect();
+    let message = format!(
+        "[{}]: {} {}",
+        "Undone".yellow(),
+        entry.command,
+        references.join(", ")
+    );
+    let tasks = get_task_outputs(&tasks, state.get_conn())?;
+    TaskRenderer::new(state.get_output_format(), false).render_message(
+        state.writer(),
+        &json!({ "command": entry.command, "tasks": tasks }),
+        &message,
+    )
+}
+
+#[cfg(test)]
+mod test_undo_app {
+    use super::*;
+
+    use crate::common::inputs::ScriptedPrompter;
+    use crate::common::storage::{
+        add_note_to_task, get_events_for_task, get_notes_for_task, get_recurrences,
+        get_tags_for_task, get_task_by_id, get_task_outputs,
+    };
+    use crate::common::testing;
+    use crate::domain::Status;
+    use crate::schema::suaide::dsl::suaide;
+    use crate::state::State;
+    use crate::subcommands::{add, close, edit, redo, remove};
+
+    use diesel::prelude::*;
+    use std::str::from_utf8;
+
+    #[test]
+    fn undoes_a_close() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches =
+            add::app().get_matches_from(vec!["add", "-d", "Test Description", "-t", "1234"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let matches = close::app().get_matches_from(vec!["close", "1234"]);
+        assert!(close::handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["undo"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let task = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(task.task_status(), Status::Open);
+        assert_eq!(task.closed, None);
+
+        let data = from_utf8(&writer).expect("should be a string here");
+        assert!(data.contains("close 1234"));
+    }
+
+    #[test]
+    fn undoes_remove_all() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+        state.set_prompter(Box::new(ScriptedPrompter::default().confirm(true)));
+
+        for ticket in &["1", "2"] {
+            let matches = add::app().get_matches_from(vec!["add", "-d", "Task", "-t", ticket]);
+            assert!(add::handler(&matches, &mut state).is_ok());
+        }
+        let before = suaide.load(state.get_conn()).unwrap();
+        let before = get_task_outputs(&before, state.get_conn()).unwrap();
+
+        let matches = remove::app().get_matches_from(vec!["remove", "--all"]);
+        assert!(remove::handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["undo"]);
+        assert!(handler(&matches, &mut state).is_ok());
+
+        let after = suaide.load(state.get_conn()).unwrap();
+        assert_eq!(get_task_outputs(&after, state.get_conn()).unwrap(), before);
+    }
+
+    #[test]
+    fn undoes_an_add() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches =
+            add::app().get_matches_from(vec!["add", "-d", "Test Description", "-t", "1234"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["undo"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(matches!(
+            get_task_by_id(1, state.get_conn()),
+            Err(SuaideError::NotFound)
+        ));
+
+        let matches = app().get_matches_from(vec!["undo"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NothingToUndoError)
+        ));
+    }
+
+    #[test]
+    fn undoing_an_add_leaves_nothing_behind() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches = add::app().get_matches_from(vec![
+            "add",
+            "-d",
+            "Test Description",
+            "--tag",
+            "backend",
+            "--recur",
+            "daily",
+        ]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        add_note_to_task(1, "A note".to_string(), 10000, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["undo"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(get_tags_for_task(1, state.get_conn()).unwrap().is_empty());
+        assert!(get_notes_for_task(1, state.get_conn()).unwrap().is_empty());
+        assert!(get_recurrences(state.get_conn()).unwrap().is_empty());
+        assert!(get_events_for_task(1, state.get_conn()).unwrap().is_empty());
+
+        // Redoing it brings back the task on its own
+        let matches = redo::app().get_matches_from(vec!["redo"]);
+        assert!(redo::handler(&matches, &mut state).is_ok());
+        let task = get_task_by_id(1, state.get_conn()).unwrap();
+        assert_eq!(task.description, "Test Description");
+        assert!(get_tags_for_task(1, state.get_conn()).unwrap().is_empty());
+        assert!(get_notes_for_task(1, state.get_conn()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn wont_take_a_ticket_back_from_another_task() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches =
+            add::app().get_matches_from(vec!["add", "-d", "Test Description", "-t", "1234"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let matches = edit::app().get_matches_from(vec!["edit", "1234"]);
+        state.set_prompter(Box::new(
+            ScriptedPrompter::default()
+                .input("Test Description")
+                .input("5678")
+                .select("Open"),
+        ));
+        assert!(edit::handler(&matches, &mut state).is_ok());
+        testing::task().ticket("1234").insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["undo"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::TicketTakenError(_))
+        ));
+        assert_eq!(
+            get_task_by_id(1, state.get_conn()).unwrap().ticket,
+            Some("5678".to_string())
+        );
+        assert_eq!(
+            get_task_by_id(2, state.get_conn()).unwrap().ticket,
+            Some("1234".to_string())
+        );
+    }
+
+    #[test]
+    fn undoes_an_edit_that_only_changed_tags() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        let matches =
+            add::app().get_matches_from(vec!["add", "-d", "Test Description", "-t", "1234"]);
+        assert!(add::handler(&matches, &mut state).is_ok());
+        let matches = edit::app().get_matches_from(vec!["edit", "1234", "--tag", "backend"]);
+        assert!(edit::handler(&matches, &mut state).is_ok());
+
+        let matches = app().get_matches_from(vec!["undo"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(get_task_by_id(1, state.get_conn()).is_ok());
+        assert!(get_tags_for_task(1, state.get_conn()).unwrap().is_empty());
+
+        let matches = redo::app().get_matches_from(vec!["redo"]);
+        assert!(redo::handler(&matches, &mut state).is_ok());
+        assert_eq!(
+            get_tags_for_task(1, state.get_conn()).unwrap(),
+            vec!["backend".to_string()]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use clap::{App, Arg, ArgMatches};
+use colored::Colorize;
+use std::io;
+
+use crate::common::render::TaskRenderer;
+use crate::common::storage::{get_task_output, remove_dependency};
+use crate::domain::{SuaideError, TaskOutput};
+use crate::state::State;
+
+pub fn app<'a>() -> App<'a, 'static> {
+    App::new("unlink")
+        .about("Remove a task blocking another")
+        .arg(
+            Arg::with_name("task")
+                .index(1)
+                .required(true)
+                .help("The task that was blocking the other")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("blocks")
+                .long("blocks")
+                .short("b")
+                .required(true)
+                .help("The task that's no longer blocked")
+                .takes_value(true),
+        )
+}
+
+pub fn handler<W: io::Write>(
+    matches: &ArgMatches,
+    state: &mut State<W>,
+) -> Result<(), SuaideError> {
+    if let (Some(task), Some(blocks)) = (matches.value_of("task"), matches.value_of("blocks")) {
+        let task = state.find_task(task)?;
+        let blocked = state.find_task(blocks)?;
+        remove_dependency(blocked.id, task.id, state.get_conn())?;
+
+        let output = get_task_output(&blocked, state.get_conn())?;
+        let message = format!(
+            "{} {} {}",
+            TaskOutput::reference(&task),
+            "no longer blocks".green(),
+            TaskOutput::reference(&blocked)
+        );
+        return TaskRenderer::new(state.get_output_format(), false).render_message(
+            state.writer(),
+            &output,
+            &message,
+        );
+    }
+    Err(SuaideError::IncorrectArgs)
+}
+
+#[cfg(test)]
+mod test_unlink_app {
+    use super::*;
+
+    use crate::common::storage::{add_dependency, get_dependencies};
+    use crate::common::testing;
+    use crate::state::State;
+
+    #[test]
+    fn unlinks_tasks() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+        add_dependency(2, 1, state.get_conn()).unwrap();
+
+        let matches = app().get_matches_from(vec!["unlink", "1", "--blocks", "2"]);
+        assert!(handler(&matches, &mut state).is_ok());
+        assert!(get_dependencies(2, state.get_conn()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_error_when_not_linked() {
+        let mut writer = Vec::new();
+        let mut state = State::new(&mut writer).unwrap();
+
+        testing::task().insert(state.get_conn());
+        testing::task().insert(state.get_conn());
+
+        let matches = app().get_matches_from(vec!["unlink", "1", "--blocks", "2"]);
+        assert!(matches!(
+            handler(&matches, &mut state),
+            Err(SuaideError::NotFound)
+        ));
+    }
+}